@@ -42,8 +42,8 @@ fn dleq_statement<CS: SchnorrCS>(
     G: CS::PointVar,
     H: CS::PointVar,
 ) {
-    cs.constrain(1, A, vec![(x, G)]);
-    cs.constrain(1, B, vec![(x, H)]);
+    cs.constrain(A, vec![(x, G)]);
+    cs.constrain(B, vec![(x, H)]);
 }
 
 #[bench]
@@ -0,0 +1,204 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::TryInto;
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+
+use crate::{BatchableProof, CompactProof, ProofError};
+
+/// A proof of either format, as carried by a [`ProofEnvelope`].
+#[derive(Clone)]
+pub enum EnvelopedProof {
+    /// A proof in compact format.
+    Compact(CompactProof),
+    /// A proof in batchable format.
+    Batchable(BatchableProof),
+}
+
+impl From<CompactProof> for EnvelopedProof {
+    fn from(proof: CompactProof) -> Self {
+        EnvelopedProof::Compact(proof)
+    }
+}
+
+impl From<BatchableProof> for EnvelopedProof {
+    fn from(proof: BatchableProof) -> Self {
+        EnvelopedProof::Batchable(proof)
+    }
+}
+
+/// A self-describing proof: the proof together with the label of its
+/// statement and the points of its statement instance.
+///
+/// Envelopes of proofs of different statements can be carried over
+/// the same channel, and routed to the verifier of their statement
+/// by label, as done by
+/// [`StatementRegistry::verify_envelope`](crate::toolbox::registry::StatementRegistry::verify_envelope).
+/// The common points of the statement are not part of the envelope,
+/// since the verifier must supply the ones it expects.
+///
+/// [`ProofEnvelope::to_bytes`] gives the canonical encoding of an
+/// envelope, which is the only encoding accepted by
+/// [`ProofEnvelope::from_bytes`].  It is the concatenation of
+///
+/// * the format version, [`ProofEnvelope::VERSION`], as one byte;
+/// * the proof kind, as one byte: `0` for compact and `1` for batchable;
+/// * the statement label, as UTF-8;
+/// * the compressed points of the instance variables, in the order
+///   they are declared;
+/// * the challenges, the commitments (for a batchable proof only),
+///   and the responses of the proof, as canonical encodings,
+///
+/// where the label and each list of points or scalars are prefixed by
+/// their length as a 32-bit little-endian integer.
+#[derive(Clone)]
+pub struct ProofEnvelope {
+    /// The label of the proof statement, such as the `PROOF_LABEL` of
+    /// a module generated by [`define_proof`].
+    pub label: String,
+    /// The compressed points of the instance variables.
+    pub instance: Vec<CompressedRistretto>,
+    /// The proof.
+    pub proof: EnvelopedProof,
+}
+
+const COMPACT: u8 = 0;
+const BATCHABLE: u8 = 1;
+
+impl ProofEnvelope {
+    /// The version of the encoding produced by [`ProofEnvelope::to_bytes`].
+    pub const VERSION: u8 = 1;
+
+    /// Put a `proof` of the statement with the given `label` and its
+    /// `instance` points in an envelope.
+    pub fn new<P: Into<EnvelopedProof>>(label: &str, instance: Vec<CompressedRistretto>, proof: P) -> Self {
+        ProofEnvelope {
+            label: String::from(label),
+            instance,
+            proof: proof.into(),
+        }
+    }
+
+    /// Encode the envelope in its canonical encoding.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.push(ProofEnvelope::VERSION);
+        bytes.push(match self.proof {
+            EnvelopedProof::Compact(_) => COMPACT,
+            EnvelopedProof::Batchable(_) => BATCHABLE,
+        });
+        write_bytes(&mut bytes, self.label.as_bytes());
+        write_list(&mut bytes, self.instance.iter().map(|point| point.as_bytes()));
+        match &self.proof {
+            EnvelopedProof::Compact(proof) => {
+                write_list(&mut bytes, proof.challenges.iter().map(Scalar::as_bytes));
+                write_list(&mut bytes, proof.responses.iter().map(Scalar::as_bytes));
+            }
+            EnvelopedProof::Batchable(proof) => {
+                write_list(&mut bytes, proof.challenges.iter().map(Scalar::as_bytes));
+                write_list(&mut bytes, proof.commitments.iter().map(|point| point.as_bytes()));
+                write_list(&mut bytes, proof.responses.iter().map(Scalar::as_bytes));
+            }
+        }
+        bytes
+    }
+
+    /// Decode an envelope from its canonical encoding.
+    ///
+    /// Returns [`ProofError::UnsupportedVersion`] if the envelope was
+    /// encoded with an unknown version of the format, and
+    /// [`ProofError::MalformedEnvelope`] if it isn't a canonical
+    /// encoding otherwise.  The points aren't checked to be valid.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ProofError> {
+        let mut reader = Reader(bytes);
+        let version = reader.byte()?;
+        if version != ProofEnvelope::VERSION {
+            return Err(ProofError::UnsupportedVersion { version });
+        }
+        let kind = reader.byte()?;
+        let label = String::from_utf8(reader.bytes()?.to_vec()).map_err(|_| ProofError::MalformedEnvelope)?;
+        let instance = reader.points()?;
+        let proof = match kind {
+            COMPACT => EnvelopedProof::Compact(CompactProof {
+                challenges: reader.scalars()?,
+                responses: reader.scalars()?,
+            }),
+            BATCHABLE => EnvelopedProof::Batchable(BatchableProof {
+                challenges: reader.scalars()?,
+                commitments: reader.points()?,
+                responses: reader.scalars()?,
+            }),
+            _ => return Err(ProofError::MalformedEnvelope),
+        };
+        if !reader.0.is_empty() {
+            return Err(ProofError::MalformedEnvelope);
+        }
+
+        Ok(ProofEnvelope { label, instance, proof })
+    }
+}
+
+fn write_len(bytes: &mut Vec<u8>, len: usize) {
+    let len: u32 = len.try_into().expect("envelope field too long");
+    bytes.extend_from_slice(&len.to_le_bytes());
+}
+
+fn write_bytes(bytes: &mut Vec<u8>, data: &[u8]) {
+    write_len(bytes, data.len());
+    bytes.extend_from_slice(data);
+}
+
+fn write_list<'a, I>(bytes: &mut Vec<u8>, items: I)
+where
+    I: ExactSizeIterator<Item = &'a [u8; 32]>,
+{
+    write_len(bytes, items.len());
+    for item in items {
+        bytes.extend_from_slice(item);
+    }
+}
+
+/// Reads the fields of an encoded envelope, failing if it ends early.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ProofError> {
+        if self.0.len() < len {
+            return Err(ProofError::MalformedEnvelope);
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn byte(&mut self) -> Result<u8, ProofError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn len(&mut self) -> Result<usize, ProofError> {
+        let len = u32::from_le_bytes(self.take(4)?.try_into().unwrap());
+        Ok(len as usize)
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8], ProofError> {
+        let len = self.len()?;
+        self.take(len)
+    }
+
+    fn list(&mut self) -> Result<impl Iterator<Item = [u8; 32]> + 'a, ProofError> {
+        let len = self.len()?;
+        let items = self.take(len.checked_mul(32).ok_or(ProofError::MalformedEnvelope)?)?;
+        Ok(items.chunks_exact(32).map(|item| item.try_into().unwrap()))
+    }
+
+    fn points(&mut self) -> Result<Vec<CompressedRistretto>, ProofError> {
+        Ok(self.list()?.map(CompressedRistretto).collect())
+    }
+
+    fn scalars(&mut self) -> Result<Vec<Scalar>, ProofError> {
+        self.list()?
+            .map(|bytes| Scalar::from_canonical_bytes(bytes).ok_or(ProofError::MalformedEnvelope))
+            .collect()
+    }
+}
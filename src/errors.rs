@@ -1,14 +1,162 @@
+use alloc::boxed::Box;
+use alloc::string::String;
+
+#[cfg(feature = "std")]
 use thiserror::Error;
+
 /// An error during proving or verification, such as a verification failure.
-#[derive(Debug, Error)]
+///
+/// Rejected proofs are reported with a variant describing the cause,
+/// to help diagnose why a proof was rejected.  The errors fall in
+/// three groups:
+///
+/// * a well-formed proof whose checks fail: [`ProofError::VerificationFailure`]
+///   and [`ProofError::ChallengeMismatch`], or the failure of one proof of a
+///   batch, [`ProofError::BatchProofFailure`], which
+///   [`ProofError::is_verification_failure`] picks out;
+/// * a malformed proof or instance, such as a wrong number of responses
+///   or an invalid point encoding, which is found before the
+///   verification equations are checked;
+/// * a misuse of the API by the caller, such as
+///   [`ProofError::BatchSizeMismatch`].
+///
+/// Callers who don't want to reveal the cause to an untrusted party
+/// should report any error from verification as a rejection.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "std", derive(Error))]
 pub enum ProofError {
     /// Something is wrong with the proof, causing a verification failure.
-    #[error("Verification failed.")]
+    ///
+    /// This is returned when the proof is well-formed, but the
+    /// verification equations don't hold.
+    #[cfg_attr(feature = "std", error("Verification failed."))]
     VerificationFailure,
     /// Occurs during batch verification if the batch parameters are mis-sized.
-    #[error("Mismatched parameter sizes for batch verification.")]
+    #[cfg_attr(feature = "std", error("Mismatched parameter sizes for batch verification."))]
     BatchSizeMismatch,
-    /// Occurs during creation of proof when not all necessary secrets are provided to fullfil OR-clause.
-    #[error("Mismatched inputs with respect to OR-clauses.")]
+    /// Occurs during creation of proof when no clause of the statement
+    /// has all of its secrets provided.
+    #[cfg_attr(feature = "std", error("Mismatched inputs with respect to OR-clauses."))]
     InputMismatch,
+    /// The assignment to the point variable with the given label is the identity.
+    #[cfg_attr(feature = "std", error("Point variable {label:?} is the identity."))]
+    IdentityPoint {
+        /// The transcript label of the point variable.
+        label: String,
+    },
+    /// The assignment to the point variable with the given label is
+    /// not a valid point encoding.
+    #[cfg_attr(feature = "std", error("Point variable {label:?} is not a valid point encoding."))]
+    InvalidPoint {
+        /// The transcript label of the point variable.
+        label: String,
+    },
+    /// The blinding factor commitment for the given constraint is the
+    /// identity or not a valid point encoding.
+    #[cfg_attr(feature = "std", error("Commitment for constraint {constraint} is invalid."))]
+    InvalidCommitment {
+        /// The index of the constraint, in the order the constraints were added.
+        constraint: usize,
+    },
+    /// The proof has a different number of responses than the
+    /// statement has secret variables in its clauses.
+    #[cfg_attr(feature = "std", error("Expected {expected} responses, found {found}."))]
+    WrongResponseCount {
+        /// The number of secret variables in each clause of the
+        /// statement, added up.
+        expected: usize,
+        /// The number of responses in the proof.
+        found: usize,
+    },
+    /// The proof has a different number of commitments than the
+    /// statement has constraints.
+    #[cfg_attr(feature = "std", error("Expected {expected} commitments, found {found}."))]
+    WrongCommitmentCount {
+        /// The number of constraints in the statement.
+        expected: usize,
+        /// The number of commitments in the proof.
+        found: usize,
+    },
+    /// The proof has a different number of challenges than expected
+    /// for the statement.
+    #[cfg_attr(feature = "std", error("Expected {expected} challenges, found {found}."))]
+    WrongChallengeCount {
+        /// The number of challenges expected for the statement.
+        expected: usize,
+        /// The number of challenges in the proof.
+        found: usize,
+    },
+    /// The challenge recomputed from the transcript differs from the
+    /// challenge in the proof.
+    #[cfg_attr(feature = "std", error("Challenge mismatch."))]
+    ChallengeMismatch,
+    /// Occurs during proving if a precommitment was made for a
+    /// different statement, secrets or points than the prover's.
+    #[cfg_attr(feature = "std", error("Precommitment doesn't match the proof statement."))]
+    PrecommitmentMismatch,
+    /// A [`ProofEnvelope`](crate::ProofEnvelope) is not a canonical
+    /// encoding of an envelope.
+    #[cfg_attr(feature = "std", error("Malformed proof envelope."))]
+    MalformedEnvelope,
+    /// A [`ProofEnvelope`](crate::ProofEnvelope) was encoded with an
+    /// unsupported version of the format.
+    #[cfg_attr(feature = "std", error("Unsupported proof envelope version {version}."))]
+    UnsupportedVersion {
+        /// The version of the envelope's encoding.
+        version: u8,
+    },
+    /// There is no statement with the label of a proof.
+    #[cfg_attr(feature = "std", error("Unknown proof statement {label:?}."))]
+    UnknownStatement {
+        /// The label of the proof's statement.
+        label: String,
+    },
+    /// The statement instance of a proof has a different number of
+    /// points than the statement has instance variables.
+    #[cfg_attr(feature = "std", error("Expected {expected} instance points, found {found}."))]
+    WrongInstanceSize {
+        /// The number of instance variables of the statement.
+        expected: usize,
+        /// The number of points in the instance.
+        found: usize,
+    },
+    /// The proof at the given index of a batch failed verification.
+    #[cfg_attr(feature = "std", error("Proof {index} of the batch failed: {source}"))]
+    BatchProofFailure {
+        /// The index of the proof in the batch.
+        index: usize,
+        /// The reason the proof failed.
+        #[cfg_attr(feature = "std", source)]
+        source: Box<ProofError>,
+    },
+}
+
+impl ProofError {
+    /// Returns `true` if this error means that a well-formed proof
+    /// failed its checks, or that a proof of a batch was rejected.
+    ///
+    /// Errors for malformed proofs and instances, and for misuse of
+    /// the API, return `false`.  A [`ProofError::BatchProofFailure`]
+    /// returns `true` whatever its `source`, which tells why that
+    /// proof was rejected.
+    pub fn is_verification_failure(&self) -> bool {
+        matches!(
+            self,
+            ProofError::VerificationFailure
+                | ProofError::ChallengeMismatch
+                | ProofError::BatchProofFailure { .. }
+        )
+    }
+
+    pub(crate) fn in_batch(self, index: usize) -> ProofError {
+        ProofError::BatchProofFailure {
+            index,
+            source: Box::new(self),
+        }
+    }
+}
+
+/// Converts a transcript label to a `String` for error reporting.
+pub(crate) fn label_string(label: &[u8]) -> String {
+    String::from_utf8_lossy(label).into_owned()
 }
@@ -9,6 +9,7 @@
 // Authors:
 // - Henry de Valence <hdevalence@hdevalence.ca>
 
+#![cfg_attr(not(feature = "std"), no_std)]
 #![allow(non_snake_case)]
 #![cfg_attr(feature = "bench", feature(test))]
 #![cfg_attr(feature = "nightly", feature(external_doc))]
@@ -20,8 +21,11 @@
 //! Docs will only build on nightly Rust until
 //! [RFC 1990 stabilizes](https://github.com/rust-lang/rust/issues/44732).
 
-extern crate serde;
-
+#[doc(hidden)]
+pub extern crate serde;
+#[doc(hidden)]
+#[macro_use]
+pub extern crate alloc;
 #[doc(hidden)]
 #[macro_use]
 pub extern crate serde_derive;
@@ -31,13 +35,18 @@ pub extern crate curve25519_dalek;
 pub extern crate merlin;
 #[doc(hidden)]
 pub extern crate rand;
+#[doc(hidden)]
+pub extern crate zeroize;
 
 pub use merlin::Transcript;
+pub use zeroize::Zeroizing;
 
+mod envelope;
 mod errors;
 mod proofs;
 mod util;
 
+pub use crate::envelope::*;
 pub use crate::errors::*;
 pub use crate::proofs::*;
 
@@ -45,4 +54,5 @@ pub mod toolbox;
 
 #[macro_use]
 mod macros;
+#[allow(unused_imports)]
 pub use crate::macros::*;
@@ -21,7 +21,301 @@ macro_rules! __compute_formula_constraint {
     // Since there's a trailing +, we can just generate the list as normal...
     (($public_vars:ident, $secret_vars:ident)
      $( $point:ident ^ $scalar:ident *)+ ) => {
-        vec![ $( ($secret_vars.$scalar , $public_vars.$point), )* ]
+        $crate::alloc::vec![ $( ($secret_vars.$scalar , $public_vars.$point), )* ]
+    };
+}
+
+/// Computes the LHS of a statement as a list of integer coefficients
+/// and public points: either a single point, or a parenthesized sum
+/// of points, each optionally raised to an integer power, such as
+/// `(A - B)` or `(C * D ^ -1)`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __compute_formula_lhs {
+    // A single point
+    (($public_vars:ident) $point:ident) => {
+        $crate::alloc::vec![ ($crate::curve25519_dalek::scalar::Scalar::one(), $public_vars.$point) ]
+    };
+    // Unbracket a combination, which may start with a minus sign
+    (($public_vars:ident) (- $($x:tt)+)) => {
+        __compute_formula_lhs!(@term $public_vars [] [-] $($x)+)
+    };
+    (($public_vars:ident) ($($x:tt)+)) => {
+        __compute_formula_lhs!(@term $public_vars [] [] $($x)+)
+    };
+    // A term, negated if the preceding operator is `-`
+    (@term $public_vars:ident [$($terms:tt)*] $sign:tt $point:ident ^ - $power:literal $($rest:tt)*) => {
+        __compute_formula_lhs!(@op $public_vars [$($terms)* ($sign (-$power), $point)] $($rest)*)
+    };
+    (@term $public_vars:ident [$($terms:tt)*] $sign:tt $point:ident ^ $power:literal $($rest:tt)*) => {
+        __compute_formula_lhs!(@op $public_vars [$($terms)* ($sign ($power), $point)] $($rest)*)
+    };
+    (@term $public_vars:ident [$($terms:tt)*] $sign:tt $point:ident $($rest:tt)*) => {
+        __compute_formula_lhs!(@op $public_vars [$($terms)* ($sign (1), $point)] $($rest)*)
+    };
+    // The operator between two terms; `*` and `+` both add points
+    (@op $public_vars:ident $terms:tt * $($rest:tt)+) => {
+        __compute_formula_lhs!(@term $public_vars $terms [] $($rest)+)
+    };
+    (@op $public_vars:ident $terms:tt + $($rest:tt)+) => {
+        __compute_formula_lhs!(@term $public_vars $terms [] $($rest)+)
+    };
+    (@op $public_vars:ident $terms:tt - $($rest:tt)+) => {
+        __compute_formula_lhs!(@term $public_vars $terms [-] $($rest)+)
+    };
+    (@op $public_vars:ident [$(([$($neg:tt)?] $power:tt, $point:ident))+]) => {
+        $crate::alloc::vec![ $(
+            ($($neg)? $crate::toolbox::__integer_coefficient $power, $public_vars.$point),
+        )+ ]
+    };
+}
+
+/// Expands the given items only if `zkp` was built with the `std`
+/// feature.  Generated code must use this instead of `#[cfg]`, which
+/// would test the features of the crate invoking the macro.
+#[cfg(feature = "std")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __cfg_std {
+    ( $($item:item)* ) => { $($item)* };
+}
+
+#[cfg(not(feature = "std"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __cfg_std {
+    ( $($item:item)* ) => {};
+}
+
+/// Expands the given items only if `zkp` was built with the `bench`
+/// feature, like [`__cfg_std`].
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __cfg_bench {
+    ( $($item:item)* ) => { $($item)* };
+}
+
+#[cfg(not(feature = "bench"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __cfg_bench {
+    ( $($item:item)* ) => {};
+}
+
+/// Derives `Serialize` and `Deserialize` for the given item, which
+/// holds secrets, only if `zkp` was built with the
+/// `serialize-witness` feature.
+#[cfg(feature = "serialize-witness")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __derive_witness_serde {
+    ( $item:item ) => {
+        #[derive(Serialize, Deserialize)]
+        #[serde(crate = "self::serde")]
+        $item
+    };
+}
+
+#[cfg(not(feature = "serialize-witness"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __derive_witness_serde {
+    ( $item:item ) => { $item };
+}
+
+/// Defines the `Witness` enum of a statement, with one variant per
+/// clause holding the secrets which occur in that clause.
+///
+/// Macros can't compare identifiers directly, so the secrets of a
+/// clause are filtered by defining a helper macro with one rule per
+/// secret occurring in the clause, which recurses once per declared
+/// secret.  Each clause is filtered in a separate expansion, which
+/// stores its secrets in a per-clause macro, so that the recursion
+/// limit isn't reached by statements with many clauses or secrets.
+///
+/// Macros can't build identifiers either, so the names of the
+/// variants and of the per-clause macros are taken from fixed lists,
+/// which limits a statement to 16 clauses.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __define_witness {
+    (@enum [$([($name:ident $clause_nr:literal) [$($secret:ident)*]])+]) => {
+        $crate::__derive_witness_serde! {
+            /// The secrets known to the prover, which satisfy one clause
+            /// of the statement.
+            ///
+            /// Each variant holds the secrets which occur in one clause,
+            /// in the order the clauses are declared.  The secrets are
+            /// zeroized when the witness is dropped.
+            ///
+            /// With the `serialize-witness` feature of `zkp`, the witness
+            /// is serializable, and **its secrets are written in the
+            /// clear**.
+            #[derive(Clone)]
+            pub enum Witness {
+                $(
+                    /// The secrets of one clause of the statement.
+                    $name { $($secret: Scalar,)* },
+                )+
+            }
+        }
+
+        impl Witness {
+            /// The number of the witness' clause, counting from 1.
+            fn clause_nr(&self) -> usize {
+                match self {
+                    $(Witness::$name { .. } => $clause_nr,)+
+                }
+            }
+
+            /// The assignments to all secret variables, with the
+            /// secrets outside of the witness' clause unassigned.
+            fn secrets(&self) -> Secrets {
+                let mut secrets = Secrets::default();
+                match self {
+                    $(Witness::$name { $($secret,)* } => {
+                        $(secrets.$secret = Some(*$secret);)*
+                    })+
+                }
+                secrets
+            }
+        }
+
+        impl $crate::zeroize::Zeroize for Witness {
+            fn zeroize(&mut self) {
+                match self {
+                    $(Witness::$name { $($secret,)* } => {
+                        $($crate::zeroize::Zeroize::zeroize($secret);)*
+                    })+
+                }
+            }
+        }
+
+        impl Drop for Witness {
+            fn drop(&mut self) {
+                $crate::zeroize::Zeroize::zeroize(self);
+            }
+        }
+
+        $crate::__cfg_bench! {
+            #[cfg(test)]
+            $crate::__define_witness!{ @random $([$name [$($secret)*]])+ }
+        }
+    };
+    (@clause $secrets:tt [] $fields:tt [$($clauses:tt)+] $done:tt) => {
+        compile_error!("define_proof! supports at most 16 alternatives");
+    };
+    (@clause $secrets:tt $names:tt $fields:tt [] [$($done:tt)+]) => {
+        $crate::__define_witness!{ @collect [] $($done)+ }
+    };
+    // Filter the secrets of each clause in its own expansion, so that
+    // the expansion depth doesn't add up over the clauses.
+    (@clause $secrets:tt [$name:tt $($names:tt)*] [$fields:ident $($rest:ident)*]
+     [[$(($($term:tt)*))*] $($clauses:tt)*] [$($done:tt)*]) => {
+        $crate::__define_witness!{ @terms ($) $secrets $fields [$($($term)* *)*] }
+        $crate::__define_witness!{
+            @clause $secrets [$($names)*] [$($rest)*] [$($clauses)*] [$($done)* [$name $fields]]
+        }
+    };
+    // Keep the declared secrets which occur in the clause, and define
+    // the macro `$fields` to pass them on.  The `$` token is passed
+    // in as `$d` to write the helpers' own matchers.
+    (@terms ($d:tt) $secrets:tt $fields:ident [$($point:ident ^ $scalar:ident *)*]) => {
+        macro_rules! __zkp_witness_filter {
+            (($d dd:tt) [$d($d kept:ident)*] []) => {
+                macro_rules! $fields {
+                    ($d dd($d dd args:tt)*) => {
+                        $crate::__define_witness!{ @collected [$d($d kept)*] $d dd($d dd args)* }
+                    };
+                }
+            };
+            $((($d dd:tt) [$d($d kept:ident)*] [$scalar $d($d todo:ident)*]) => {
+                __zkp_witness_filter!{ ($d dd) [$d($d kept)* $scalar] [$d($d todo)*] }
+            };)*
+            (($d dd:tt) [$d($d kept:ident)*] [$d secret:ident $d($d todo:ident)*]) => {
+                __zkp_witness_filter!{ ($d dd) [$d($d kept)*] [$d($d todo)*] }
+            };
+        }
+        __zkp_witness_filter!{ ($d) [] $secrets }
+    };
+    // Read back the secrets of each clause.
+    (@collect $collected:tt [$name:tt $fields:ident] $($done:tt)*) => {
+        $fields!{ $collected $name $($done)* }
+    };
+    (@collect [$($collected:tt)+]) => {
+        $crate::__define_witness!{ @enum [$($collected)+] }
+    };
+    (@collected $kept:tt [$($collected:tt)*] $name:tt $($done:tt)*) => {
+        $crate::__define_witness!{ @collect [$($collected)* [$name $kept]] $($done)* }
+    };
+    // A witness for the first clause with random secrets, for the
+    // generated benchmarks.
+    (@random [$name:ident [$($secret:ident)*]] $($clauses:tt)*) => {
+        impl Witness {
+            fn random<R: RngCore + CryptoRng>(rng: &mut R) -> Witness {
+                Witness::$name { $($secret: Scalar::random(rng),)* }
+            }
+        }
+    };
+    // Entry point: the declared secrets, and the statements of each
+    // clause.  This comes last, since it matches any input.
+    ($secrets:tt $($clause:tt)+) => {
+        $crate::__define_witness!{
+            @clause $secrets
+            [(Clause1 1) (Clause2 2) (Clause3 3) (Clause4 4) (Clause5 5) (Clause6 6)
+             (Clause7 7) (Clause8 8) (Clause9 9) (Clause10 10) (Clause11 11) (Clause12 12)
+             (Clause13 13) (Clause14 14) (Clause15 15) (Clause16 16)]
+            [__zkp_witness_clause1 __zkp_witness_clause2 __zkp_witness_clause3
+             __zkp_witness_clause4 __zkp_witness_clause5 __zkp_witness_clause6
+             __zkp_witness_clause7 __zkp_witness_clause8 __zkp_witness_clause9
+             __zkp_witness_clause10 __zkp_witness_clause11 __zkp_witness_clause12
+             __zkp_witness_clause13 __zkp_witness_clause14 __zkp_witness_clause15
+             __zkp_witness_clause16]
+            [$($clause)+] []
+        }
+    };
+}
+
+/// Generates a test checking that the statement of a `define_proof!`
+/// module has no issues, unless the statement is declared with
+/// `#[skip_statement_analysis]`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __statement_analysis_test {
+    (test) => {
+        #[cfg(test)]
+        #[test]
+        fn statement_analysis() {
+            let issues = internal::analyze_statement();
+            assert!(issues.is_empty(), "{:?} has issues: {:?}", internal::PROOF_LABEL, issues);
+        }
+    };
+    (skip) => {};
+}
+
+/// Normalizes the statement recorded in `cs`, if the `define_proof!`
+/// statement is declared with the `#[normalize]` flag.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __normalize_statement {
+    (normalize $cs:ident) => {
+        $cs.normalize();
+    };
+    (as_written $cs:ident) => {};
+}
+
+/// Adds the clauses of a statement to the constraint system `cs`: a
+/// single clause as a conjunction, and several clauses as the
+/// alternatives of a disjunction.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __proof_statement_clauses {
+    ($cs:ident, $clause:block) => {
+        $cs.and(|$cs| $clause)
+    };
+    ($cs:ident, $($clause:block)||+) => {
+        $cs.or(|$cs| { $( $cs.and(|$cs| $clause); )+ })
     };
 }
 
@@ -72,7 +366,47 @@ macro_rules! __parse_subroutine {
 /// Statements have the form `LHS = (A ^ x * B ^ y * C ^ z * ... )`,
 /// where `LHS` is one of the points listed as a public parameter, and
 /// the right-hand side is a sum of public points multiplied by secret
-/// scalars.
+/// scalars.  The `LHS` can also be a parenthesized combination of
+/// public points with integer coefficients, such as `(A - B)` or
+/// `(C * D ^ -1)`, which the verifier computes in the same
+/// multiscalar multiplication as the rest of the check.  Statements are combined with `&&`, and alternatives
+/// with `||`; the generated statement function adds them to the
+/// constraint system with [`SchnorrCS::and`](crate::toolbox::SchnorrCS::and)
+/// and [`SchnorrCS::or`](crate::toolbox::SchnorrCS::or).
+///
+/// The prover's secrets are passed as a generated `Witness` enum,
+/// with one variant `Clause1`, `Clause2`, ... for each alternative,
+/// holding exactly the secrets which occur in it.  For instance, a
+/// proof of `A = (G ^ x) || B = (G ^ y)` knowing `y` is made with
+/// `Witness::Clause2 { y }`.  Macros can't build the names of the
+/// variants, so a statement has at most 16 alternatives; more are a
+/// compile error.
+///
+/// Besides proving and verifying, each module has `sign` and
+/// `verify_signature`, which use the statement as a signature of
+/// knowledge on a message.
+/// Its unit type `Statement` implements the
+/// [`Statement`](crate::toolbox::Statement) trait, for code which is
+/// generic over the statement.
+///
+/// Statements declared with the `#[normalize]` flag are normalized
+/// by the generated provers and verifiers, as with
+/// [`Prover::normalize`](crate::toolbox::prover::Prover::normalize),
+/// which removes repeated constraints from the proof.  This changes
+/// the proofs, so both sides must use the same flag.
+///
+/// `internal::analyze` checks the statement for common mistakes,
+/// such as a secret which appears in no constraint, a constraint
+/// repeated in the same clause, or the same point on the LHS under
+/// two labels.  The issues it reports don't prevent proving.  Each
+/// module has a generated `statement_analysis` test, which fails if
+/// `internal::analyze_statement` finds issues in the statement; to
+/// skip it, start the statement with a `#[skip_statement_analysis]`
+/// flag.  Flags can be combined:
+///
+/// ```rust,ignore
+/// define_proof! {#[skip_statement_analysis] #[normalize] dup, "Dup", (x), (A), (G) : A = (G ^ x) && A = (G ^ x) }
+/// ```
 ///
 /// Points which have the same assignment for all instances of the
 /// proof statement (for instance, a basepoint) should be specified as
@@ -83,7 +417,20 @@ macro_rules! __parse_subroutine {
 /// variable-time code.
 #[macro_export]
 macro_rules! define_proof {
+    // Collect the flags of the statement into a fixed record:
+    // whether to generate the statement analysis test, and whether
+    // to normalize the statement.
+    (@flags [$analysis:tt $normalize:tt] #[skip_statement_analysis] $($rest:tt)*) => {
+        $crate::define_proof!{ @flags [skip $normalize] $($rest)* }
+    };
+    (@flags [$analysis:tt $normalize:tt] #[normalize] $($rest:tt)*) => {
+        $crate::define_proof!{ @flags [$analysis normalize] $($rest)* }
+    };
+    (@flags $flags:tt #[$flag:meta] $($rest:tt)*) => {
+        compile_error!(concat!("unknown define_proof! flag `", stringify!($flag), "`"));
+    };
     (
+        @flags [$analysis:tt $normalize:tt]
         $proof_module_name:ident // Name of the module to create
         ,
         $proof_label_string:expr // A string literal, used as a domain separator
@@ -100,13 +447,42 @@ macro_rules! define_proof {
     ) => {
         /// An auto-generated Schnorr proof implementation.
         ///
-        /// Proofs are created using `prove_compact` or
+        /// Proofs are created from a `Witness` using `prove_compact` or
         /// `prove_batchable`, producing `CompactProof`s or
         /// `BatchableProof`s respectively.  These are verified
         /// using `verify_compact` and `verify_batchable`;
         /// `BatchableProofs` can also be batch-verified using
         /// `batch_verify`, but they have slightly larger proof
-        /// sizes compared to `CompactProof`s.
+        /// sizes compared to `CompactProof`s, into which they can be
+        /// converted with `batchable_to_compact`.  If a batch fails,
+        /// `batch_verify_find_invalid` identifies the invalid proofs.
+        /// Large batches can be spread over several threads with
+        /// `batch_verify_parallel`.
+        /// To batch-verify proofs of different statements together,
+        /// add them to a `MultiBatchVerifier` with `add_to_multi_batch`.
+        /// `batch_prove_compact` and `batch_prove_batchable` create many
+        /// proofs in one call, sharing the setup work between them.
+        /// To create or verify many proofs with the same common points,
+        /// build a `PreparedProver` or `PreparedVerifier` once and reuse it;
+        /// a `PreparedProver` can also create many proofs in one call,
+        /// and a `PreparedVerifier` can check a stream of proofs in
+        /// bounded memory with a `StreamingVerifier`.
+        ///
+        /// To move the expensive part of proving out of the critical
+        /// path, `precommit` computes a single-use `Precommitment`
+        /// ahead of time, which `prove_compact_with_precommitment` or
+        /// `prove_batchable_with_precommitment` consume later.
+        ///
+        /// The public points of a proof can be stored or sent next to
+        /// it as a serializable `Instance`, and combined with the
+        /// verifier's `CommonPoints` to verify it later.  `envelope`
+        /// bundles both in a self-describing `ProofEnvelope`, checked
+        /// by `verify_envelope`.
+        ///
+        /// Each randomized function has a `_with_rng` variant which
+        /// takes its randomness from the caller instead of `thread_rng()`.
+        /// Without the `std` feature of `zkp`, only the `_with_rng`
+        /// variants are generated.
         ///
         /// The internal details of the proof statement are accessible
         /// in the `internals` module.  While this is not necessary
@@ -118,12 +494,22 @@ macro_rules! define_proof {
             use $crate::curve25519_dalek::scalar::Scalar;
             use $crate::curve25519_dalek::ristretto::RistrettoPoint;
             use $crate::curve25519_dalek::ristretto::CompressedRistretto;
-
+            use $crate::alloc::string::{String, ToString};
+            use $crate::alloc::vec::Vec;
+            use $crate::rand::{CryptoRng, RngCore};
+            // Used by the serde derives, with `#[serde(crate = "self::serde")]`,
+            // so that the invoking crate doesn't need to depend on serde
+            use $crate::serde;
+            use $crate::serde_derive::{Deserialize, Serialize};
+
+            use $crate::toolbox::batch_verifier::BatchVerifier;
             use $crate::toolbox::prover::Prover;
             use $crate::toolbox::verifier::Verifier;
 
             pub use $crate::merlin::Transcript;
-            pub use $crate::{CompactProof, BatchableProof, ProofError};
+            pub use $crate::{CompactProof, BatchableProof, EnvelopedProof, ProofEnvelope, ProofError};
+            pub use $crate::toolbox::multi_batch_verifier::MultiBatchVerifier;
+            pub use $crate::toolbox::prover::Precommitment;
 
             /// The generated [`internal`] module contains lower-level
             /// functions at the level of the Schnorr constraint
@@ -135,6 +521,9 @@ macro_rules! define_proof {
                 /// The proof label committed to the transcript as a domain separator.
                 pub const PROOF_LABEL: &'static str = $proof_label_string;
 
+                /// The proof statement, as written in [`define_proof`].
+                pub const DESCRIPTION: &'static str = stringify!($($($lhs $(= $statement)?)&&+)||*);
+
                 /// A container type that holds transcript labels for secret variables.
                 pub struct TranscriptLabels {
                     $( pub $secret_var: &'static str, )+
@@ -167,21 +556,22 @@ macro_rules! define_proof {
                 /// This function exists separately from the proving
                 /// and verification functions to allow composition of
                 /// different proof statements with common variable
-                /// assignments.
+                /// assignments.  If the statement is declared with the
+                /// `#[normalize]` flag, the generated provers and
+                /// verifiers normalize it after recording it, so a
+                /// constraint system which is to interoperate with them
+                /// must be normalized too.
                 pub fn proof_statement<CS: SchnorrCS>(
                     cs: &mut CS,
                     secrets: SecretVars<CS>,
                     publics: PublicVars<CS>,
                 ) {
-                    //TODO: process ORs differently
-                    let mut clause_nr = 1;
-                    $($(
+                    __proof_statement_clauses!(cs, $({$(
                         let mut subroutine = true;
                         $(
                             subroutine = false;
-                            cs.constrain(
-                                clause_nr,
-                                publics.$lhs,
+                            cs.constrain_combination(
+                                __compute_formula_lhs!( (publics) $lhs ),
                                 __compute_formula_constraint!( (publics, secrets) $statement ),
                             );
                         )?
@@ -191,17 +581,73 @@ macro_rules! define_proof {
                                 cs.add_subroutine(result.unwrap());
                             }
                         }
-                    )+
-                    clause_nr += 1;
-                    )*
+                    )+})||*);
+                }
+
+                /// Record the proof statement with the given points and list
+                /// its issues, such as secrets which appear in no constraint.
+                pub fn analyze(
+                    assignments: super::VerifyAssignments,
+                ) -> $crate::alloc::vec::Vec<$crate::toolbox::analysis::Issue> {
+                    analyze_assignments(Some(assignments))
+                }
+
+                /// Like [`analyze`], but without the points of the
+                /// statement, so the checks which depend on them are
+                /// skipped.
+                pub fn analyze_statement() -> $crate::alloc::vec::Vec<$crate::toolbox::analysis::Issue> {
+                    analyze_assignments(None)
+                }
+
+                fn analyze_assignments(
+                    assignments: Option<super::VerifyAssignments>,
+                ) -> $crate::alloc::vec::Vec<$crate::toolbox::analysis::Issue> {
+                    let mut analyzer = $crate::toolbox::analysis::StatementAnalyzer::new();
+
+                    let secret_vars = SecretVars {
+                        $($secret_var: analyzer.allocate_scalar(TRANSCRIPT_LABELS.$secret_var.as_bytes()),)+
+                    };
+
+                    let public_vars = PublicVars {
+                        $($instance_var: analyzer.allocate_point(
+                            TRANSCRIPT_LABELS.$instance_var.as_bytes(),
+                            assignments.map(|assignments| *assignments.$instance_var),
+                        ),)*
+                        $($common_var: analyzer.allocate_point(
+                            TRANSCRIPT_LABELS.$common_var.as_bytes(),
+                            assignments.map(|assignments| *assignments.$common_var),
+                        ),)*
+                    };
+
+                    proof_statement(&mut analyzer, secret_vars, public_vars);
+
+                    analyzer.analyze()
+                }
+            }
+
+            $crate::__statement_analysis_test!{ $analysis }
+
+            $crate::__define_witness!{ [$($secret_var)+] $([$($($statement)?)+])* }
+
+            /// The assignments to the secret variables given by a [`Witness`].
+            #[derive(Default)]
+            struct Secrets {
+                $($secret_var: Option<Scalar>,)+
+            }
 
+            impl Drop for Secrets {
+                fn drop(&mut self) {
+                    $($crate::zeroize::Zeroize::zeroize(&mut self.$secret_var);)+
                 }
             }
 
             /// Named parameters for [`prove_compact`] and [`prove_batchable`].
+            ///
+            /// The `witness` holds the secrets of the clause the proof
+            /// is made for.
             #[derive(Copy, Clone)]
             pub struct ProveAssignments<'a> {
-                $(pub $secret_var: &'a Option<Scalar>,)+
+                pub witness: &'a Witness,
                 $(pub $instance_var: &'a RistrettoPoint,)*
                 $(pub $common_var: &'a RistrettoPoint,)*
             }
@@ -217,20 +663,138 @@ macro_rules! define_proof {
             ///
             /// This is used to allow a prover to avoid having to
             /// re-compress points used in the proof that may be
-            /// necessary to supply to the verifier.
-            #[derive(Copy, Clone)]
+            /// necessary to supply to the verifier.  It is also the
+            /// owned form of [`VerifyAssignments`], which can be stored
+            /// next to a proof.
+            #[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+            #[serde(crate = "self::serde")]
             pub struct CompressedPoints {
                 $(pub $instance_var: CompressedRistretto,)*
                 $(pub $common_var: CompressedRistretto,)*
             }
 
+            impl CompressedPoints {
+                /// Combine the points of a statement [`Instance`] with the [`CommonPoints`].
+                #[allow(unused_variables)]
+                pub fn from_parts(instance: &Instance, common: &CommonPoints) -> Self {
+                    CompressedPoints {
+                        $($instance_var: instance.$instance_var,)*
+                        $($common_var: common.$common_var,)*
+                    }
+                }
+
+                /// The points of the instance variables.
+                pub fn instance(&self) -> Instance {
+                    Instance {
+                        $($instance_var: self.$instance_var,)*
+                    }
+                }
+
+                /// The points of the common variables.
+                pub fn common(&self) -> CommonPoints {
+                    CommonPoints {
+                        $($common_var: self.$common_var,)*
+                    }
+                }
+
+                /// Borrow the points as the assignments of a verifier.
+                pub fn verify_assignments(&self) -> VerifyAssignments<'_> {
+                    VerifyAssignments {
+                        $($instance_var: &self.$instance_var,)*
+                        $($common_var: &self.$common_var,)*
+                    }
+                }
+            }
+
+            /// The assignments to the instance variables of one proof.
+            ///
+            /// Together with the [`CommonPoints`] the verifier expects,
+            /// this is a self-contained statement instance, which can be
+            /// stored or sent next to a proof and verified later.
+            #[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+            #[serde(crate = "self::serde")]
+            pub struct Instance {
+                $(pub $instance_var: CompressedRistretto,)*
+            }
+
+            impl Instance {
+                /// The points of the instance variables, in the order they are declared.
+                pub fn points(&self) -> Vec<CompressedRistretto> {
+                    $crate::alloc::vec![$(self.$instance_var),*]
+                }
+
+                /// Assign the instance variables from their points, in the order they are declared.
+                #[allow(unused_mut, unused_variables)]
+                pub fn from_points(points: &[CompressedRistretto]) -> Result<Self, ProofError> {
+                    let names: &[&str] = &[$(stringify!($instance_var)),*];
+                    let expected = names.len();
+                    if points.len() != expected {
+                        return Err(ProofError::WrongInstanceSize {
+                            expected,
+                            found: points.len(),
+                        });
+                    }
+                    let mut points = points.iter();
+                    Ok(Instance {
+                        $($instance_var: *points.next().unwrap(),)*
+                    })
+                }
+            }
+
+            /// The assignments to the common variables, which are the
+            /// same for all instances of the statement.
+            #[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+            #[serde(crate = "self::serde")]
+            pub struct CommonPoints {
+                $(pub $common_var: CompressedRistretto,)*
+            }
+
             /// Named parameters for [`batch_verify`].
-            #[derive(Clone)]
+            #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+            #[serde(crate = "self::serde")]
             pub struct BatchVerifyAssignments {
                 $(pub $instance_var: Vec<CompressedRistretto>,)*
                 $(pub $common_var: CompressedRistretto,)*
             }
 
+            impl BatchVerifyAssignments {
+                /// Collect the assignments for a batch of statement
+                /// instances with the same [`CommonPoints`].
+                #[allow(unused_variables)]
+                pub fn from_instances(instances: &[Instance], common: &CommonPoints) -> Self {
+                    BatchVerifyAssignments {
+                        $($instance_var: instances.iter().map(|instance| instance.$instance_var).collect(),)*
+                        $($common_var: common.$common_var,)*
+                    }
+                }
+            }
+
+            $crate::__derive_witness_serde! {
+                /// The owned form of [`ProveAssignments`].
+                ///
+                /// It holds the prover's [`Witness`].  With the
+                /// `serialize-witness` feature of `zkp`, it is
+                /// serializable, and **the secrets are written in the
+                /// clear**.
+                #[derive(Clone)]
+                pub struct OwnedProveAssignments {
+                    pub witness: Witness,
+                    $(pub $instance_var: RistrettoPoint,)*
+                    $(pub $common_var: RistrettoPoint,)*
+                }
+            }
+
+            impl OwnedProveAssignments {
+                /// Borrow the assignments as the assignments of a prover.
+                pub fn prove_assignments(&self) -> ProveAssignments<'_> {
+                    ProveAssignments {
+                        witness: &self.witness,
+                        $($instance_var: &self.$instance_var,)*
+                        $($common_var: &self.$common_var,)*
+                    }
+                }
+            }
+
             fn build_prover<'a>(
                 transcript: &'a mut Transcript,
                 assignments: ProveAssignments,
@@ -240,11 +804,13 @@ macro_rules! define_proof {
 
                 let mut prover = Prover::new(PROOF_LABEL.as_bytes(), transcript);
 
+                let secrets = assignments.witness.secrets();
+                prover.set_real_clause(assignments.witness.clause_nr());
                 let secret_vars = SecretVars {
                     $(
                         $secret_var: prover.allocate_scalar(
                             TRANSCRIPT_LABELS.$secret_var.as_bytes(),
-                            *assignments.$secret_var,
+                            secrets.$secret_var,
                         ),
                     )+
                 };
@@ -281,104 +847,719 @@ macro_rules! define_proof {
                 };
 
                 proof_statement(&mut prover, secret_vars, public_vars);
+                $crate::__normalize_statement!{ $normalize prover }
 
                 (prover, compressed)
             }
 
-            /// Given a transcript and assignments to secret and public variables, produce a proof in compact format.
-            pub fn prove_compact(
+            $crate::__cfg_std! {
+                /// Given a transcript and assignments to secret and public variables, produce a proof in compact format.
+                pub fn prove_compact(
+                    transcript: &mut Transcript,
+                    assignments: ProveAssignments,
+                ) -> Result<(CompactProof, CompressedPoints), ProofError> {
+                    prove_compact_with_rng(transcript, assignments, &mut $crate::rand::thread_rng())
+                }
+            }
+
+            /// Like [`prove_compact`], but hedges the blinding factors with randomness from `rng`.
+            pub fn prove_compact_with_rng<R: RngCore + CryptoRng>(
                 transcript: &mut Transcript,
                 assignments: ProveAssignments,
+                rng: &mut R,
             ) -> Result<(CompactProof, CompressedPoints), ProofError> {
                 let (prover, compressed) = build_prover(transcript, assignments);
 
-                let result = prover.prove_compact();
-                if result.is_err() {
-                    return Err(result.err().unwrap());
+                let proof = prover.prove_compact_with_rng(rng)?;
+                Ok((proof, compressed))
+            }
+
+            $crate::__cfg_std! {
+                /// Given a transcript and assignments to secret and public variables, produce a proof in batchable format.
+                pub fn prove_batchable(
+                    transcript: &mut Transcript,
+                    assignments: ProveAssignments,
+                ) -> Result<(BatchableProof, CompressedPoints), ProofError> {
+                    prove_batchable_with_rng(transcript, assignments, &mut $crate::rand::thread_rng())
                 }
-                Ok((result.unwrap(), compressed))
             }
 
-            /// Given a transcript and assignments to secret and public variables, produce a proof in batchable format.
-            pub fn prove_batchable(
+            /// Like [`prove_batchable`], but hedges the blinding factors with randomness from `rng`.
+            pub fn prove_batchable_with_rng<R: RngCore + CryptoRng>(
                 transcript: &mut Transcript,
                 assignments: ProveAssignments,
+                rng: &mut R,
             ) -> Result<(BatchableProof, CompressedPoints), ProofError> {
                 let (prover, compressed) = build_prover(transcript, assignments);
 
-                let result = prover.prove_batchable();
-                if result.is_err() {
-                    return Err(result.err().unwrap());
+                let proof = prover.prove_batchable_with_rng(rng)?;
+                Ok((proof, compressed))
+            }
+
+            $crate::__cfg_std! {
+                /// Given a message, a transcript and assignments to secret and public variables,
+                /// produce a signature of knowledge of the secrets on the message.
+                ///
+                /// The signature is a proof in compact format, bound to the message, which is
+                /// appended to the transcript with
+                /// [`TranscriptProtocol::append_signed_message`]($crate::toolbox::TranscriptProtocol::append_signed_message).
+                pub fn sign(
+                    message: &[u8],
+                    transcript: &mut Transcript,
+                    assignments: ProveAssignments,
+                ) -> Result<(CompactProof, CompressedPoints), ProofError> {
+                    sign_with_rng(message, transcript, assignments, &mut $crate::rand::thread_rng())
                 }
-                Ok((result.unwrap(), compressed))
             }
 
-            fn build_verifier<'a>(
-                transcript: &'a mut Transcript,
-                assignments: VerifyAssignments,
-            ) -> Result<Verifier<'a>, ProofError> {
+            /// Like [`sign`], but hedges the blinding factors with randomness from `rng`.
+            pub fn sign_with_rng<R: RngCore + CryptoRng>(
+                message: &[u8],
+                transcript: &mut Transcript,
+                assignments: ProveAssignments,
+                rng: &mut R,
+            ) -> Result<(CompactProof, CompressedPoints), ProofError> {
+                use $crate::toolbox::TranscriptProtocol;
+
+                transcript.append_signed_message(message);
+                prove_compact_with_rng(transcript, assignments, rng)
+            }
+
+            $crate::__cfg_std! {
+                /// Given assignments to secret and public variables, perform the offline phase of proving.
+                ///
+                /// The returned [`Precommitment`] can be used once, with the same assignments, to produce a
+                /// proof with [`prove_compact_with_precommitment`] or [`prove_batchable_with_precommitment`].
+                pub fn precommit(assignments: ProveAssignments) -> Result<Precommitment, ProofError> {
+                    precommit_with_rng(assignments, &mut $crate::rand::thread_rng())
+                }
+            }
+
+            /// Like [`precommit`], but generates the blinding factors with randomness from `rng`.
+            ///
+            /// A precommitment isn't bound to the proof it's later used for, so `rng` must be a
+            /// fresh CSPRNG and never one seeded for reproducibility: using two precommitments
+            /// with the same nonces leaks the secrets. With the `std` feature of `zkp`, fresh
+            /// randomness from `thread_rng()` is mixed in as well.
+            pub fn precommit_with_rng<R: RngCore + CryptoRng>(
+                assignments: ProveAssignments,
+                rng: &mut R,
+            ) -> Result<Precommitment, ProofError> {
+                let mut transcript = Transcript::new(b"zkp precommitment");
+                let (prover, _compressed) = build_prover(&mut transcript, assignments);
+
+                prover.precommit_with_rng(rng)
+            }
+
+            /// Given a transcript, assignments to secret and public variables, and a precommitment
+            /// made for the same assignments, perform the online phase of proving in compact format.
+            pub fn prove_compact_with_precommitment(
+                transcript: &mut Transcript,
+                assignments: ProveAssignments,
+                precommitment: Precommitment,
+            ) -> Result<(CompactProof, CompressedPoints), ProofError> {
+                let (prover, compressed) = build_prover(transcript, assignments);
+
+                let proof = prover.prove_compact_with_precommitment(precommitment)?;
+                Ok((proof, compressed))
+            }
+
+            /// Given a transcript, assignments to secret and public variables, and a precommitment
+            /// made for the same assignments, perform the online phase of proving in batchable format.
+            pub fn prove_batchable_with_precommitment(
+                transcript: &mut Transcript,
+                assignments: ProveAssignments,
+                precommitment: Precommitment,
+            ) -> Result<(BatchableProof, CompressedPoints), ProofError> {
+                let (prover, compressed) = build_prover(transcript, assignments);
+
+                let proof = prover.prove_batchable_with_precommitment(precommitment)?;
+                Ok((proof, compressed))
+            }
+
+            fn build_batch_prover<'a>(
+                transcripts: Vec<&'a mut Transcript>,
+                assignments: &[ProveAssignments],
+            ) -> Result<($crate::toolbox::batch_prover::BatchProver<'a>, Vec<CompressedPoints>), ProofError> {
                 use self::internal::*;
-                use $crate::toolbox::verifier::*;
+                use $crate::toolbox::batch_prover::BatchProver;
 
-                let mut verifier = Verifier::new(PROOF_LABEL.as_bytes(), transcript);
+                let mut prover = BatchProver::new(PROOF_LABEL.as_bytes(), assignments.len(), transcripts)?;
 
+                let secrets = assignments.iter().map(|a| a.witness.secrets()).collect::<Vec<_>>();
+                prover.set_real_clauses(assignments.iter().map(|a| a.witness.clause_nr()).collect())?;
                 let secret_vars = SecretVars {
-                    $($secret_var: verifier.allocate_scalar(TRANSCRIPT_LABELS.$secret_var.as_bytes()),)+
+                    $(
+                        $secret_var: prover.allocate_scalar(
+                            TRANSCRIPT_LABELS.$secret_var.as_bytes(),
+                            secrets.iter().map(|s| s.$secret_var).collect(),
+                        )?,
+                    )+
                 };
 
-                let public_vars = PublicVars {
+                struct VarPointPairs {
+                    $( pub $instance_var: ($crate::toolbox::prover::PointVar, Vec<CompressedRistretto>), )*
+                    $( pub $common_var: ($crate::toolbox::prover::PointVar, Vec<CompressedRistretto>), )*
+                }
+
+                // Common points are shared by the batch if they are
+                // the same in every assignment
+                let pairs = VarPointPairs {
                     $(
-                        $instance_var: verifier.allocate_point(
+                        $instance_var: prover.allocate_instance_point(
                             TRANSCRIPT_LABELS.$instance_var.as_bytes(),
-                            *assignments.$instance_var,
+                            assignments.iter().map(|a| *a.$instance_var).collect(),
                         )?,
                     )*
                     $(
-                        $common_var: verifier.allocate_point(
+                        $common_var: prover.allocate_point(
                             TRANSCRIPT_LABELS.$common_var.as_bytes(),
-                            *assignments.$common_var,
+                            assignments.iter().map(|a| *a.$common_var).collect(),
                         )?,
                     )*
                 };
 
-                proof_statement(&mut verifier, secret_vars, public_vars);
+                let public_vars = PublicVars {
+                    $($instance_var: pairs.$instance_var.0,)*
+                    $($common_var: pairs.$common_var.0,)*
+                };
 
-                Ok(verifier)
-            }
+                let compressed = (0..assignments.len())
+                    .map(|_j| CompressedPoints {
+                        $($instance_var: pairs.$instance_var.1[_j],)*
+                        $($common_var: pairs.$common_var.1[_j],)*
+                    })
+                    .collect();
 
-            /// Given a transcript and assignments to public variables, verify a proof in compact format.
-            pub fn verify_compact(
-                proof: &CompactProof,
-                transcript: &mut Transcript,
-                assignments: VerifyAssignments,
-            ) -> Result<(), ProofError> {
-                let verifier = build_verifier(transcript, assignments)?;
+                proof_statement(&mut prover, secret_vars, public_vars);
+                $crate::__normalize_statement!{ $normalize prover }
 
-                verifier.verify_compact(proof)
+                Ok((prover, compressed))
             }
 
-            /// Given a transcript and assignments to public variables, verify a proof in batchable format.
-            pub fn verify_batchable(
-                proof: &BatchableProof,
-                transcript: &mut Transcript,
-                assignments: VerifyAssignments,
+            $crate::__cfg_std! {
+                /// Given one transcript and one set of assignments to secret and public variables per proof,
+                /// produce many proofs in compact format, sharing the setup work between them.
+                pub fn batch_prove_compact(
+                    transcripts: Vec<&mut Transcript>,
+                    assignments: &[ProveAssignments],
+                ) -> Result<Vec<(CompactProof, CompressedPoints)>, ProofError> {
+                    batch_prove_compact_with_rng(transcripts, assignments, &mut $crate::rand::thread_rng())
+                }
+
+                /// Given one transcript and one set of assignments to secret and public variables per proof,
+                /// produce many proofs in batchable format, sharing the setup work between them.
+                pub fn batch_prove_batchable(
+                    transcripts: Vec<&mut Transcript>,
+                    assignments: &[ProveAssignments],
+                ) -> Result<Vec<(BatchableProof, CompressedPoints)>, ProofError> {
+                    batch_prove_batchable_with_rng(transcripts, assignments, &mut $crate::rand::thread_rng())
+                }
+
+                /// Produce proofs like [`batch_prove_compact`], using up to `num_threads` threads.
+                pub fn batch_prove_compact_parallel(
+                    transcripts: Vec<&mut Transcript>,
+                    assignments: &[ProveAssignments],
+                    num_threads: usize,
+                ) -> Result<Vec<(CompactProof, CompressedPoints)>, ProofError> {
+                    batch_prove_compact_parallel_with_rng(
+                        transcripts,
+                        assignments,
+                        num_threads,
+                        &mut $crate::rand::thread_rng(),
+                    )
+                }
+
+                /// Like [`batch_prove_compact_parallel`], but generates the blinding factors with randomness
+                /// from `rng`, seeding one rng per thread from it.
+                pub fn batch_prove_compact_parallel_with_rng<R: RngCore + CryptoRng>(
+                    transcripts: Vec<&mut Transcript>,
+                    assignments: &[ProveAssignments],
+                    num_threads: usize,
+                    rng: &mut R,
+                ) -> Result<Vec<(CompactProof, CompressedPoints)>, ProofError> {
+                    let (prover, compressed) = build_batch_prover(transcripts, assignments)?;
+
+                    let proofs = prover.prove_compact_parallel_with_rng(num_threads, rng)?;
+                    Ok(proofs.into_iter().zip(compressed).collect())
+                }
+
+                /// Produce proofs like [`batch_prove_batchable`], using up to `num_threads` threads.
+                pub fn batch_prove_batchable_parallel(
+                    transcripts: Vec<&mut Transcript>,
+                    assignments: &[ProveAssignments],
+                    num_threads: usize,
+                ) -> Result<Vec<(BatchableProof, CompressedPoints)>, ProofError> {
+                    batch_prove_batchable_parallel_with_rng(
+                        transcripts,
+                        assignments,
+                        num_threads,
+                        &mut $crate::rand::thread_rng(),
+                    )
+                }
+
+                /// Like [`batch_prove_batchable_parallel`], but generates the blinding factors with randomness
+                /// from `rng`, seeding one rng per thread from it.
+                pub fn batch_prove_batchable_parallel_with_rng<R: RngCore + CryptoRng>(
+                    transcripts: Vec<&mut Transcript>,
+                    assignments: &[ProveAssignments],
+                    num_threads: usize,
+                    rng: &mut R,
+                ) -> Result<Vec<(BatchableProof, CompressedPoints)>, ProofError> {
+                    let (prover, compressed) = build_batch_prover(transcripts, assignments)?;
+
+                    let proofs = prover.prove_batchable_parallel_with_rng(num_threads, rng)?;
+                    Ok(proofs.into_iter().zip(compressed).collect())
+                }
+            }
+
+            /// Like [`batch_prove_compact`], but hedges the blinding factors with randomness from `rng`.
+            pub fn batch_prove_compact_with_rng<R: RngCore + CryptoRng>(
+                transcripts: Vec<&mut Transcript>,
+                assignments: &[ProveAssignments],
+                rng: &mut R,
+            ) -> Result<Vec<(CompactProof, CompressedPoints)>, ProofError> {
+                let (prover, compressed) = build_batch_prover(transcripts, assignments)?;
+
+                let proofs = prover.prove_compact_with_rng(rng)?;
+                Ok(proofs.into_iter().zip(compressed).collect())
+            }
+
+            /// Like [`batch_prove_batchable`], but hedges the blinding factors with randomness from `rng`.
+            pub fn batch_prove_batchable_with_rng<R: RngCore + CryptoRng>(
+                transcripts: Vec<&mut Transcript>,
+                assignments: &[ProveAssignments],
+                rng: &mut R,
+            ) -> Result<Vec<(BatchableProof, CompressedPoints)>, ProofError> {
+                let (prover, compressed) = build_batch_prover(transcripts, assignments)?;
+
+                let proofs = prover.prove_batchable_with_rng(rng)?;
+                Ok(proofs.into_iter().zip(compressed).collect())
+            }
+
+            /// Precomputed tables for the common variables, for [`PreparedProver::new`].
+            #[derive(Clone)]
+            pub struct CommonTables {
+                $(pub $common_var: $crate::curve25519_dalek::ristretto::RistrettoBasepointTable,)*
+            }
+
+            /// Named parameters for the proving methods of [`PreparedProver`].
+            ///
+            /// These are the assignments to the secret and instance
+            /// variables; the common variables are taken from the
+            /// prover's tables.
+            #[derive(Copy, Clone)]
+            pub struct ProveInstanceAssignments<'a> {
+                pub witness: &'a Witness,
+                $(pub $instance_var: &'a RistrettoPoint,)*
+            }
+
+            /// A prover for this statement which uses precomputed tables
+            /// for the common variables, and can be reused for many proofs.
+            ///
+            /// The proofs are the same as those produced by [`prove_compact`]
+            /// and [`prove_batchable`] with the tables' basepoints assigned to
+            /// the common variables.
+            #[derive(Clone)]
+            pub struct PreparedProver {
+                tables: CommonTables,
+            }
+
+            impl PreparedProver {
+                /// Prepare a prover using the given tables for the common variables.
+                pub fn new(tables: CommonTables) -> Self {
+                    PreparedProver { tables }
+                }
+
+                fn build_prover<'a>(
+                    &'a self,
+                    transcript: &'a mut Transcript,
+                    assignments: ProveInstanceAssignments,
+                ) -> (Prover<'a>, CompressedPoints) {
+                    use self::internal::*;
+
+                    let mut prover = Prover::new(PROOF_LABEL.as_bytes(), transcript);
+
+                    let secrets = assignments.witness.secrets();
+                    prover.set_real_clause(assignments.witness.clause_nr());
+                prover.set_real_clause(assignments.witness.clause_nr());
+                    let secret_vars = SecretVars {
+                        $(
+                            $secret_var: prover.allocate_scalar(
+                                TRANSCRIPT_LABELS.$secret_var.as_bytes(),
+                                secrets.$secret_var,
+                            ),
+                        )+
+                    };
+
+                    struct VarPointPairs {
+                        $( pub $instance_var: ($crate::toolbox::prover::PointVar, CompressedRistretto), )*
+                        $( pub $common_var: ($crate::toolbox::prover::PointVar, CompressedRistretto), )*
+                    }
+
+                    let pairs = VarPointPairs {
+                        $(
+                            $instance_var: prover.allocate_point(
+                                TRANSCRIPT_LABELS.$instance_var.as_bytes(),
+                                *assignments.$instance_var,
+                            ),
+                        )*
+                        $(
+                            $common_var: prover.allocate_point_with_table(
+                                TRANSCRIPT_LABELS.$common_var.as_bytes(),
+                                &self.tables.$common_var,
+                            ),
+                        )*
+                    };
+
+                    let public_vars = PublicVars {
+                        $($instance_var: pairs.$instance_var.0,)*
+                        $($common_var: pairs.$common_var.0,)*
+                    };
+
+                    let compressed = CompressedPoints {
+                        $($instance_var: pairs.$instance_var.1,)*
+                        $($common_var: pairs.$common_var.1,)*
+                    };
+
+                    proof_statement(&mut prover, secret_vars, public_vars);
+                    $crate::__normalize_statement!{ $normalize prover }
+
+                    (prover, compressed)
+                }
+
+                $crate::__cfg_std! {
+                    /// Given a transcript and assignments to the secret and instance variables, produce a proof in compact format.
+                    pub fn prove_compact(
+                        &self,
+                        transcript: &mut Transcript,
+                        assignments: ProveInstanceAssignments,
+                    ) -> Result<(CompactProof, CompressedPoints), ProofError> {
+                        self.prove_compact_with_rng(transcript, assignments, &mut $crate::rand::thread_rng())
+                    }
+                }
+
+                /// Like [`PreparedProver::prove_compact`], but hedges the blinding factors with randomness from `rng`.
+                pub fn prove_compact_with_rng<R: RngCore + CryptoRng>(
+                    &self,
+                    transcript: &mut Transcript,
+                    assignments: ProveInstanceAssignments,
+                    rng: &mut R,
+                ) -> Result<(CompactProof, CompressedPoints), ProofError> {
+                    let (prover, compressed) = self.build_prover(transcript, assignments);
+
+                    let proof = prover.prove_compact_with_rng(rng)?;
+                    Ok((proof, compressed))
+                }
+
+                $crate::__cfg_std! {
+                    /// Given a transcript and assignments to the secret and instance variables, produce a proof in batchable format.
+                    pub fn prove_batchable(
+                        &self,
+                        transcript: &mut Transcript,
+                        assignments: ProveInstanceAssignments,
+                    ) -> Result<(BatchableProof, CompressedPoints), ProofError> {
+                        self.prove_batchable_with_rng(transcript, assignments, &mut $crate::rand::thread_rng())
+                    }
+                }
+
+                /// Like [`PreparedProver::prove_batchable`], but hedges the blinding factors with randomness from `rng`.
+                pub fn prove_batchable_with_rng<R: RngCore + CryptoRng>(
+                    &self,
+                    transcript: &mut Transcript,
+                    assignments: ProveInstanceAssignments,
+                    rng: &mut R,
+                ) -> Result<(BatchableProof, CompressedPoints), ProofError> {
+                    let (prover, compressed) = self.build_prover(transcript, assignments);
+
+                    let proof = prover.prove_batchable_with_rng(rng)?;
+                    Ok((proof, compressed))
+                }
+
+                fn build_batch_prover<'a>(
+                    &'a self,
+                    transcripts: Vec<&'a mut Transcript>,
+                    assignments: &[ProveInstanceAssignments],
+                ) -> Result<($crate::toolbox::batch_prover::BatchProver<'a>, Vec<CompressedPoints>), ProofError> {
+                    use self::internal::*;
+                    use $crate::toolbox::batch_prover::BatchProver;
+
+                    let mut prover = BatchProver::new(PROOF_LABEL.as_bytes(), assignments.len(), transcripts)?;
+
+                    let secrets = assignments.iter().map(|a| a.witness.secrets()).collect::<Vec<_>>();
+                    prover.set_real_clauses(assignments.iter().map(|a| a.witness.clause_nr()).collect())?;
+                prover.set_real_clauses(assignments.iter().map(|a| a.witness.clause_nr()).collect())?;
+                    let secret_vars = SecretVars {
+                        $(
+                            $secret_var: prover.allocate_scalar(
+                                TRANSCRIPT_LABELS.$secret_var.as_bytes(),
+                                secrets.iter().map(|s| s.$secret_var).collect(),
+                            )?,
+                        )+
+                    };
+
+                    struct VarPointPairs {
+                        $( pub $instance_var: ($crate::toolbox::prover::PointVar, Vec<CompressedRistretto>), )*
+                        $( pub $common_var: ($crate::toolbox::prover::PointVar, CompressedRistretto), )*
+                    }
+
+                    let pairs = VarPointPairs {
+                        $(
+                            $instance_var: prover.allocate_instance_point(
+                                TRANSCRIPT_LABELS.$instance_var.as_bytes(),
+                                assignments.iter().map(|a| *a.$instance_var).collect(),
+                            )?,
+                        )*
+                        $(
+                            $common_var: prover.allocate_static_point_with_table(
+                                TRANSCRIPT_LABELS.$common_var.as_bytes(),
+                                &self.tables.$common_var,
+                            ),
+                        )*
+                    };
+
+                    let public_vars = PublicVars {
+                        $($instance_var: pairs.$instance_var.0,)*
+                        $($common_var: pairs.$common_var.0,)*
+                    };
+
+                    let compressed = (0..assignments.len())
+                        .map(|_j| CompressedPoints {
+                            $($instance_var: pairs.$instance_var.1[_j],)*
+                            $($common_var: pairs.$common_var.1,)*
+                        })
+                        .collect();
+
+                    proof_statement(&mut prover, secret_vars, public_vars);
+                    $crate::__normalize_statement!{ $normalize prover }
+
+                    Ok((prover, compressed))
+                }
+
+                $crate::__cfg_std! {
+                    /// Given one transcript and one set of assignments to the secret and instance variables per proof,
+                    /// produce many proofs in compact format, sharing the setup work between them.
+                    pub fn batch_prove_compact(
+                        &self,
+                        transcripts: Vec<&mut Transcript>,
+                        assignments: &[ProveInstanceAssignments],
+                    ) -> Result<Vec<(CompactProof, CompressedPoints)>, ProofError> {
+                        self.batch_prove_compact_with_rng(transcripts, assignments, &mut $crate::rand::thread_rng())
+                    }
+
+                    /// Given one transcript and one set of assignments to the secret and instance variables per proof,
+                    /// produce many proofs in batchable format, sharing the setup work between them.
+                    pub fn batch_prove_batchable(
+                        &self,
+                        transcripts: Vec<&mut Transcript>,
+                        assignments: &[ProveInstanceAssignments],
+                    ) -> Result<Vec<(BatchableProof, CompressedPoints)>, ProofError> {
+                        self.batch_prove_batchable_with_rng(transcripts, assignments, &mut $crate::rand::thread_rng())
+                    }
+
+                    /// Produce proofs like [`PreparedProver::batch_prove_compact`], using up to `num_threads` threads.
+                    pub fn batch_prove_compact_parallel(
+                        &self,
+                        transcripts: Vec<&mut Transcript>,
+                        assignments: &[ProveInstanceAssignments],
+                        num_threads: usize,
+                    ) -> Result<Vec<(CompactProof, CompressedPoints)>, ProofError> {
+                        self.batch_prove_compact_parallel_with_rng(
+                            transcripts,
+                            assignments,
+                            num_threads,
+                            &mut $crate::rand::thread_rng(),
+                        )
+                    }
+
+                    /// Like [`PreparedProver::batch_prove_compact_parallel`], but generates the blinding factors
+                    /// with randomness from `rng`, seeding one rng per thread from it.
+                    pub fn batch_prove_compact_parallel_with_rng<R: RngCore + CryptoRng>(
+                        &self,
+                        transcripts: Vec<&mut Transcript>,
+                        assignments: &[ProveInstanceAssignments],
+                        num_threads: usize,
+                        rng: &mut R,
+                    ) -> Result<Vec<(CompactProof, CompressedPoints)>, ProofError> {
+                        let (prover, compressed) = self.build_batch_prover(transcripts, assignments)?;
+
+                        let proofs = prover.prove_compact_parallel_with_rng(num_threads, rng)?;
+                        Ok(proofs.into_iter().zip(compressed).collect())
+                    }
+
+                    /// Produce proofs like [`PreparedProver::batch_prove_batchable`], using up to `num_threads` threads.
+                    pub fn batch_prove_batchable_parallel(
+                        &self,
+                        transcripts: Vec<&mut Transcript>,
+                        assignments: &[ProveInstanceAssignments],
+                        num_threads: usize,
+                    ) -> Result<Vec<(BatchableProof, CompressedPoints)>, ProofError> {
+                        self.batch_prove_batchable_parallel_with_rng(
+                            transcripts,
+                            assignments,
+                            num_threads,
+                            &mut $crate::rand::thread_rng(),
+                        )
+                    }
+
+                    /// Like [`PreparedProver::batch_prove_batchable_parallel`], but generates the blinding factors
+                    /// with randomness from `rng`, seeding one rng per thread from it.
+                    pub fn batch_prove_batchable_parallel_with_rng<R: RngCore + CryptoRng>(
+                        &self,
+                        transcripts: Vec<&mut Transcript>,
+                        assignments: &[ProveInstanceAssignments],
+                        num_threads: usize,
+                        rng: &mut R,
+                    ) -> Result<Vec<(BatchableProof, CompressedPoints)>, ProofError> {
+                        let (prover, compressed) = self.build_batch_prover(transcripts, assignments)?;
+
+                        let proofs = prover.prove_batchable_parallel_with_rng(num_threads, rng)?;
+                        Ok(proofs.into_iter().zip(compressed).collect())
+                    }
+                }
+
+                /// Like [`PreparedProver::batch_prove_compact`], but hedges the blinding factors with randomness from `rng`.
+                pub fn batch_prove_compact_with_rng<R: RngCore + CryptoRng>(
+                    &self,
+                    transcripts: Vec<&mut Transcript>,
+                    assignments: &[ProveInstanceAssignments],
+                    rng: &mut R,
+                ) -> Result<Vec<(CompactProof, CompressedPoints)>, ProofError> {
+                    let (prover, compressed) = self.build_batch_prover(transcripts, assignments)?;
+
+                    let proofs = prover.prove_compact_with_rng(rng)?;
+                    Ok(proofs.into_iter().zip(compressed).collect())
+                }
+
+                /// Like [`PreparedProver::batch_prove_batchable`], but hedges the blinding factors with randomness from `rng`.
+                pub fn batch_prove_batchable_with_rng<R: RngCore + CryptoRng>(
+                    &self,
+                    transcripts: Vec<&mut Transcript>,
+                    assignments: &[ProveInstanceAssignments],
+                    rng: &mut R,
+                ) -> Result<Vec<(BatchableProof, CompressedPoints)>, ProofError> {
+                    let (prover, compressed) = self.build_batch_prover(transcripts, assignments)?;
+
+                    let proofs = prover.prove_batchable_with_rng(rng)?;
+                    Ok(proofs.into_iter().zip(compressed).collect())
+                }
+            }
+
+            fn build_verifier<'a>(
+                transcript: &'a mut Transcript,
+                assignments: VerifyAssignments,
+            ) -> Result<Verifier<'a>, ProofError> {
+                use self::internal::*;
+                use $crate::toolbox::verifier::*;
+
+                let mut verifier = Verifier::new(PROOF_LABEL.as_bytes(), transcript);
+
+                let secret_vars = SecretVars {
+                    $($secret_var: verifier.allocate_scalar(TRANSCRIPT_LABELS.$secret_var.as_bytes()),)+
+                };
+
+                let public_vars = PublicVars {
+                    $(
+                        $instance_var: verifier.allocate_point(
+                            TRANSCRIPT_LABELS.$instance_var.as_bytes(),
+                            *assignments.$instance_var,
+                        )?,
+                    )*
+                    $(
+                        $common_var: verifier.allocate_point(
+                            TRANSCRIPT_LABELS.$common_var.as_bytes(),
+                            *assignments.$common_var,
+                        )?,
+                    )*
+                };
+
+                proof_statement(&mut verifier, secret_vars, public_vars);
+                $crate::__normalize_statement!{ $normalize verifier }
+
+                Ok(verifier)
+            }
+
+            /// Given a transcript and assignments to public variables, verify a proof in compact format.
+            pub fn verify_compact(
+                proof: &CompactProof,
+                transcript: &mut Transcript,
+                assignments: VerifyAssignments,
             ) -> Result<(), ProofError> {
                 let verifier = build_verifier(transcript, assignments)?;
 
-                verifier.verify_batchable(proof)
+                verifier.verify_compact(proof)
             }
 
-            /// Verify a batch of proofs, given a batch of transcripts and a batch of assignments.
-            pub fn batch_verify(
-                proofs: &[BatchableProof],
-                transcripts: Vec<&mut Transcript>,
-                assignments: BatchVerifyAssignments,
+            /// Given a transcript and assignments to public variables, check a proof in batchable format
+            /// and convert it to the proof in compact format for the same statement and transcript.
+            pub fn batchable_to_compact(
+                proof: &BatchableProof,
+                transcript: &mut Transcript,
+                assignments: VerifyAssignments,
+            ) -> Result<CompactProof, ProofError> {
+                let verifier = build_verifier(transcript, assignments)?;
+
+                verifier.compact_from_batchable(proof)
+            }
+
+            /// Given a message, a transcript and assignments to public variables, verify a
+            /// signature produced by [`sign`].
+            pub fn verify_signature(
+                message: &[u8],
+                signature: &CompactProof,
+                transcript: &mut Transcript,
+                assignments: VerifyAssignments,
             ) -> Result<(), ProofError> {
+                use $crate::toolbox::TranscriptProtocol;
+
+                transcript.append_signed_message(message);
+                verify_compact(signature, transcript, assignments)
+            }
+
+            $crate::__cfg_std! {
+                /// Given a transcript and assignments to public variables, verify a proof in batchable format.
+                pub fn verify_batchable(
+                    proof: &BatchableProof,
+                    transcript: &mut Transcript,
+                    assignments: VerifyAssignments,
+                ) -> Result<(), ProofError> {
+                    verify_batchable_with_rng(proof, transcript, assignments, &mut $crate::rand::thread_rng())
+                }
+            }
+
+            /// Like [`verify_batchable`], but draws the verifier's random factors from `rng`.
+            pub fn verify_batchable_with_rng<R: RngCore + CryptoRng>(
+                proof: &BatchableProof,
+                transcript: &mut Transcript,
+                assignments: VerifyAssignments,
+                rng: &mut R,
+            ) -> Result<(), ProofError> {
+                let verifier = build_verifier(transcript, assignments)?;
+
+                verifier.verify_batchable_with_rng(proof, rng)
+            }
+
+            /// Add a proof in batchable format to a [`MultiBatchVerifier`],
+            /// to be verified together with proofs of other statements.
+            pub fn add_to_multi_batch(
+                multi_verifier: &mut MultiBatchVerifier,
+                proof: &BatchableProof,
+                transcript: &mut Transcript,
+                assignments: VerifyAssignments,
+            ) -> Result<(), ProofError> {
+                let verifier = build_verifier(transcript, assignments)?;
+
+                multi_verifier.add_proof(verifier, proof)
+            }
+
+            fn build_batch_verifier<'a>(
+                batch_size: usize,
+                transcripts: Vec<&'a mut Transcript>,
+                assignments: BatchVerifyAssignments,
+            ) -> Result<BatchVerifier<'a>, ProofError> {
                 use self::internal::*;
                 use $crate::toolbox::batch_verifier::*;
 
-                let batch_size = proofs.len();
-
                 let mut verifier = BatchVerifier::new(PROOF_LABEL.as_bytes(), batch_size, transcripts)?;
 
                 let secret_vars = SecretVars {
@@ -401,127 +1582,492 @@ macro_rules! define_proof {
                 };
 
                 proof_statement(&mut verifier, secret_vars, public_vars);
+                $crate::__normalize_statement!{ $normalize verifier }
 
-                verifier.verify_batchable(proofs)
+                Ok(verifier)
             }
 
-            pub fn output_latex_protocol() -> String {
-                "PoK$\\{(x) : A = g^x\\}$".to_string() //TODO: implement!
+            $crate::__cfg_std! {
+                /// Verify a batch of proofs, given a batch of transcripts and a batch of assignments.
+                pub fn batch_verify(
+                    proofs: &[BatchableProof],
+                    transcripts: Vec<&mut Transcript>,
+                    assignments: BatchVerifyAssignments,
+                ) -> Result<(), ProofError> {
+                    batch_verify_with_rng(proofs, transcripts, assignments, &mut $crate::rand::thread_rng())
+                }
             }
 
-            pub fn measure() -> usize {
-                5 //TODO: implement! I guess it's not gonna be usize as output, but some Measurement object
+            /// Like [`batch_verify`], but draws the verifier's random factors from `rng`.
+            pub fn batch_verify_with_rng<R: RngCore + CryptoRng>(
+                proofs: &[BatchableProof],
+                transcripts: Vec<&mut Transcript>,
+                assignments: BatchVerifyAssignments,
+                rng: &mut R,
+            ) -> Result<(), ProofError> {
+                let verifier = build_batch_verifier(proofs.len(), transcripts, assignments)?;
+
+                verifier.verify_batchable_with_rng(proofs, rng)
             }
 
-            #[cfg(all(feature = "bench", test))]
-            mod bench {
-                use super::*;
-                use $crate::rand::thread_rng;
+            $crate::__cfg_std! {
+                /// Verify a batch of proofs like [`batch_verify`], using up to `num_threads` threads.
+                pub fn batch_verify_parallel(
+                    proofs: &[BatchableProof],
+                    transcripts: Vec<&mut Transcript>,
+                    assignments: BatchVerifyAssignments,
+                    num_threads: usize,
+                ) -> Result<(), ProofError> {
+                    batch_verify_parallel_with_rng(
+                        proofs,
+                        transcripts,
+                        assignments,
+                        num_threads,
+                        &mut $crate::rand::thread_rng(),
+                    )
+                }
 
-                extern crate test;
-                use self::test::Bencher;
+                /// Like [`batch_verify_parallel`], but draws the verifier's random factors from `rng`.
+                pub fn batch_verify_parallel_with_rng<R: RngCore + CryptoRng>(
+                    proofs: &[BatchableProof],
+                    transcripts: Vec<&mut Transcript>,
+                    assignments: BatchVerifyAssignments,
+                    num_threads: usize,
+                    rng: &mut R,
+                ) -> Result<(), ProofError> {
+                    let verifier = build_batch_verifier(proofs.len(), transcripts, assignments)?;
+
+                    verifier.verify_batchable_parallel_with_rng(proofs, num_threads, rng)
+                }
+            }
 
-                #[bench]
-                fn prove(b: &mut Bencher) {
-                    let mut rng = thread_rng();
+            $crate::__cfg_std! {
+                /// Batch-verify proofs like [`batch_verify`], returning the indices of the invalid proofs.
+                ///
+                /// An empty list means that every proof in the batch is valid.
+                pub fn batch_verify_find_invalid(
+                    proofs: &[BatchableProof],
+                    transcripts: Vec<&mut Transcript>,
+                    assignments: BatchVerifyAssignments,
+                ) -> Result<Vec<usize>, ProofError> {
+                    batch_verify_find_invalid_with_rng(proofs, transcripts, assignments, &mut $crate::rand::thread_rng())
+                }
+            }
 
-                    struct RandomAssignments {
-                        $(pub $secret_var: Scalar,)+
-                        $(pub $instance_var: RistrettoPoint,)*
-                        $(pub $common_var: RistrettoPoint,)*
+            /// Like [`batch_verify_find_invalid`], but draws the verifier's random factors from `rng`.
+            pub fn batch_verify_find_invalid_with_rng<R: RngCore + CryptoRng>(
+                proofs: &[BatchableProof],
+                transcripts: Vec<&mut Transcript>,
+                assignments: BatchVerifyAssignments,
+                rng: &mut R,
+            ) -> Result<Vec<usize>, ProofError> {
+                let verifier = build_batch_verifier(proofs.len(), transcripts, assignments)?;
+
+                verifier.find_invalid_proofs_with_rng(proofs, rng)
+            }
+
+            /// Put a proof of this statement and its instance in a [`ProofEnvelope`].
+            pub fn envelope<P: Into<EnvelopedProof>>(proof: P, instance: &Instance) -> ProofEnvelope {
+                ProofEnvelope::new(internal::PROOF_LABEL, instance.points(), proof)
+            }
+
+            /// Given a transcript and the common points the verifier expects, verify the proof
+            /// in a [`ProofEnvelope`] for this statement.
+            ///
+            /// A batchable proof is verified exactly, as by [`batchable_to_compact`], so this
+            /// needs no randomness.
+            pub fn verify_envelope(
+                envelope: &ProofEnvelope,
+                transcript: &mut Transcript,
+                common: &CommonPoints,
+            ) -> Result<(), ProofError> {
+                if envelope.label != internal::PROOF_LABEL {
+                    return Err(ProofError::UnknownStatement {
+                        label: envelope.label.clone(),
+                    });
+                }
+                let instance = Instance::from_points(&envelope.instance)?;
+                let points = CompressedPoints::from_parts(&instance, common);
+
+                match &envelope.proof {
+                    EnvelopedProof::Compact(proof) => {
+                        verify_compact(proof, transcript, points.verify_assignments())
+                    }
+                    EnvelopedProof::Batchable(proof) => {
+                        batchable_to_compact(proof, transcript, points.verify_assignments()).map(|_| ())
                     }
+                }
+            }
+
+            /// The statement of this module, implementing the
+            /// [`Statement`]($crate::toolbox::Statement) trait with the
+            /// functions of the module.
+            #[derive(Copy, Clone, Debug, Default)]
+            pub struct Statement;
+
+            impl $crate::toolbox::Statement for Statement {
+                const PROOF_LABEL: &'static str = internal::PROOF_LABEL;
+                const DESCRIPTION: &'static str = internal::DESCRIPTION;
+
+                type ProveAssignments<'a> = ProveAssignments<'a>;
+                type VerifyAssignments<'a> = VerifyAssignments<'a>;
+                type BatchVerifyAssignments = BatchVerifyAssignments;
+                type CompressedPoints = CompressedPoints;
+                type CompactProof = CompactProof;
+                type BatchableProof = BatchableProof;
+                type Instance = Instance;
+                type CommonPoints = CommonPoints;
+
+                fn prove_compact_with_rng<R: RngCore + CryptoRng>(
+                    transcript: &mut Transcript,
+                    assignments: ProveAssignments,
+                    rng: &mut R,
+                ) -> Result<(CompactProof, CompressedPoints), ProofError> {
+                    prove_compact_with_rng(transcript, assignments, rng)
+                }
+
+                fn prove_batchable_with_rng<R: RngCore + CryptoRng>(
+                    transcript: &mut Transcript,
+                    assignments: ProveAssignments,
+                    rng: &mut R,
+                ) -> Result<(BatchableProof, CompressedPoints), ProofError> {
+                    prove_batchable_with_rng(transcript, assignments, rng)
+                }
+
+                fn verify_compact(
+                    proof: &CompactProof,
+                    transcript: &mut Transcript,
+                    assignments: VerifyAssignments,
+                ) -> Result<(), ProofError> {
+                    verify_compact(proof, transcript, assignments)
+                }
+
+                fn verify_batchable_with_rng<R: RngCore + CryptoRng>(
+                    proof: &BatchableProof,
+                    transcript: &mut Transcript,
+                    assignments: VerifyAssignments,
+                    rng: &mut R,
+                ) -> Result<(), ProofError> {
+                    verify_batchable_with_rng(proof, transcript, assignments, rng)
+                }
+
+                fn batch_verify_with_rng<R: RngCore + CryptoRng>(
+                    proofs: &[BatchableProof],
+                    transcripts: Vec<&mut Transcript>,
+                    assignments: BatchVerifyAssignments,
+                    rng: &mut R,
+                ) -> Result<(), ProofError> {
+                    batch_verify_with_rng(proofs, transcripts, assignments, rng)
+                }
+
+                fn verify_envelope(
+                    envelope: &ProofEnvelope,
+                    transcript: &mut Transcript,
+                    common: &CommonPoints,
+                ) -> Result<(), ProofError> {
+                    verify_envelope(envelope, transcript, common)
+                }
+            }
+
+            /// Named parameters for [`PreparedVerifier::new`].
+            #[derive(Copy, Clone)]
+            pub struct CommonAssignments {
+                $(pub $common_var: CompressedRistretto,)*
+            }
+
+            /// Named parameters for the verification methods of [`PreparedVerifier`].
+            #[derive(Copy, Clone)]
+            pub struct InstanceAssignments {
+                $(pub $instance_var: CompressedRistretto,)*
+            }
+
+            /// A verifier for this statement with fixed assignments to the
+            /// common variables, which can be reused for many proofs.
+            ///
+            /// The common points are decompressed once, and the verifier
+            /// uses precomputed tables for them.
+            pub struct PreparedVerifier {
+                statement: $crate::toolbox::prepared_verifier::PreparedStatement,
+            }
 
-                    let assignments = RandomAssignments {
-                        $($secret_var: Scalar::random(&mut rng),)+
-                        $($instance_var: RistrettoPoint::random(&mut rng),)*
-                        $($common_var: RistrettoPoint::random(&mut rng),)*
+            impl PreparedVerifier {
+                /// Prepare a verifier for the given assignments to the common variables.
+                pub fn new(assignments: CommonAssignments) -> Result<Self, ProofError> {
+                    use self::internal::*;
+
+                    let mut verifier = $crate::toolbox::prepared_verifier::PreparedVerifier::new(
+                        PROOF_LABEL.as_bytes(),
+                    );
+
+                    let secret_vars = SecretVars {
+                        $($secret_var: verifier.allocate_scalar(TRANSCRIPT_LABELS.$secret_var.as_bytes()),)+
                     };
 
-                    // Proving is constant time, so it shouldn't matter
-                    // that the relation is not satisfied by random assignments.
-                    b.iter(|| {
-                        let mut trans = Transcript::new(b"Benchmark");
-                        prove_compact(&mut trans, ProveAssignments {
-                            $($secret_var: &assignments.$secret_var,)+
-                            $($instance_var: &assignments.$instance_var,)*
-                            $($common_var: &assignments.$common_var,)*
-                        })
-                    });
+                    let public_vars = PublicVars {
+                        $(
+                            $instance_var: verifier.allocate_instance_point(
+                                TRANSCRIPT_LABELS.$instance_var.as_bytes(),
+                            ),
+                        )*
+                        $(
+                            $common_var: verifier.allocate_static_point(
+                                TRANSCRIPT_LABELS.$common_var.as_bytes(),
+                                assignments.$common_var,
+                            )?,
+                        )*
+                    };
+
+                    proof_statement(&mut verifier, secret_vars, public_vars);
+                    $crate::__normalize_statement!{ $normalize verifier }
+
+                    Ok(PreparedVerifier {
+                        statement: verifier.prepare()?,
+                    })
                 }
 
-                #[bench]
-                fn verify_compact_proof(b: &mut Bencher) {
-                    let mut rng = thread_rng();
+                fn instance_points(assignments: &InstanceAssignments) -> Vec<CompressedRistretto> {
+                    $crate::alloc::vec![$(assignments.$instance_var),*]
+                }
 
-                    struct RandomAssignments {
-                        $(pub $secret_var: Scalar,)+
-                        $(pub $instance_var: RistrettoPoint,)*
-                        $(pub $common_var: RistrettoPoint,)*
+                $crate::__cfg_std! {
+                    /// Given a transcript and assignments to the instance variables, verify a proof in batchable format.
+                    pub fn verify_batchable(
+                        &self,
+                        proof: &BatchableProof,
+                        transcript: &mut Transcript,
+                        assignments: InstanceAssignments,
+                    ) -> Result<(), ProofError> {
+                        self.verify_batchable_with_rng(proof, transcript, assignments, &mut $crate::rand::thread_rng())
                     }
+                }
 
-                    let assignments = RandomAssignments {
-                        $($secret_var: Scalar::random(&mut rng),)+
-                        $($instance_var: RistrettoPoint::random(&mut rng),)*
-                        $($common_var: RistrettoPoint::random(&mut rng),)*
-                    };
+                /// Like [`PreparedVerifier::verify_batchable`], but draws the verifier's random factors from `rng`.
+                pub fn verify_batchable_with_rng<R: RngCore + CryptoRng>(
+                    &self,
+                    proof: &BatchableProof,
+                    transcript: &mut Transcript,
+                    assignments: InstanceAssignments,
+                    rng: &mut R,
+                ) -> Result<(), ProofError> {
+                    let points = Self::instance_points(&assignments);
+                    self.statement.verify_batchable_with_rng(proof, transcript, &points, rng)
+                }
 
-                    let mut trans = Transcript::new(b"Benchmark");
-                    let (proof, points) = prove_compact(&mut trans, ProveAssignments {
-                        $($secret_var: &assignments.$secret_var,)+
-                        $($instance_var: &assignments.$instance_var,)*
-                        $($common_var: &assignments.$common_var,)*
-                    });
+                $crate::__cfg_std! {
+                    /// Verify a batch of proofs, given a batch of transcripts and assignments to the instance variables.
+                    pub fn batch_verify(
+                        &self,
+                        proofs: &[BatchableProof],
+                        transcripts: Vec<&mut Transcript>,
+                        assignments: &[InstanceAssignments],
+                    ) -> Result<(), ProofError> {
+                        self.batch_verify_with_rng(proofs, transcripts, assignments, &mut $crate::rand::thread_rng())
+                    }
+                }
 
-                    // The proof is well-formed but invalid, so the
-                    // compact verification should fall through to the
-                    // final check on the recomputed challenge, and
-                    // therefore verification failure should not affect
-                    // timing.
-                    b.iter(|| {
-                        let mut trans = Transcript::new(b"Benchmark");
-                        verify_compact(&proof, &mut trans, VerifyAssignments {
-                            $($instance_var: &points.$instance_var,)*
-                            $($common_var: &points.$common_var,)*
-                        })
-                    });
+                /// Like [`PreparedVerifier::batch_verify`], but draws the verifier's random factors from `rng`.
+                pub fn batch_verify_with_rng<R: RngCore + CryptoRng>(
+                    &self,
+                    proofs: &[BatchableProof],
+                    transcripts: Vec<&mut Transcript>,
+                    assignments: &[InstanceAssignments],
+                    rng: &mut R,
+                ) -> Result<(), ProofError> {
+                    let points: Vec<_> = assignments.iter().map(Self::instance_points).collect();
+                    self.statement.batch_verify_with_rng(proofs, transcripts, &points, rng)
+                }
+
+                /// Start a [`StreamingVerifier`] which checks proofs in chunks of `chunk_size`.
+                pub fn streaming_verifier(&self, chunk_size: usize) -> StreamingVerifier<'_> {
+                    StreamingVerifier {
+                        inner: $crate::toolbox::streaming_verifier::StreamingVerifier::new(
+                            &self.statement,
+                            chunk_size,
+                        ),
+                    }
                 }
+            }
 
-                #[bench]
-                fn verify_batchable_proof(b: &mut Bencher) {
-                    let mut rng = thread_rng();
+            /// A verifier for a stream of proofs of this statement,
+            /// which holds at most one chunk of proofs at a time.
+            ///
+            /// It is created by [`PreparedVerifier::streaming_verifier`].
+            pub struct StreamingVerifier<'s> {
+                inner: $crate::toolbox::streaming_verifier::StreamingVerifier<'s>,
+            }
 
-                    struct RandomAssignments {
-                        $(pub $secret_var: Scalar,)+
-                        $(pub $instance_var: RistrettoPoint,)*
-                        $(pub $common_var: RistrettoPoint,)*
+            impl<'s> StreamingVerifier<'s> {
+                /// The number of proofs added to the stream so far.
+                pub fn len(&self) -> usize {
+                    self.inner.len()
+                }
+
+                /// Whether no proofs have been added to the stream.
+                pub fn is_empty(&self) -> bool {
+                    self.inner.is_empty()
+                }
+
+                $crate::__cfg_std! {
+                    /// Add a proof to the stream, given its transcript and assignments to the instance variables.
+                    ///
+                    /// Returns an error if the proof is malformed, or if it completes a chunk which fails verification.
+                    pub fn push(
+                        &mut self,
+                        proof: &BatchableProof,
+                        transcript: &mut Transcript,
+                        assignments: InstanceAssignments,
+                    ) -> Result<(), ProofError> {
+                        self.push_with_rng(proof, transcript, assignments, &mut $crate::rand::thread_rng())
                     }
+                }
 
-                    let assignments = RandomAssignments {
-                        $($secret_var: Scalar::random(&mut rng),)+
-                        $($instance_var: RistrettoPoint::random(&mut rng),)*
-                        $($common_var: RistrettoPoint::random(&mut rng),)*
-                    };
+                /// Like [`StreamingVerifier::push`], but draws the verifier's random factors from `rng`.
+                pub fn push_with_rng<R: RngCore + CryptoRng>(
+                    &mut self,
+                    proof: &BatchableProof,
+                    transcript: &mut Transcript,
+                    assignments: InstanceAssignments,
+                    rng: &mut R,
+                ) -> Result<(), ProofError> {
+                    let points = PreparedVerifier::instance_points(&assignments);
+                    self.inner.push_with_rng(proof, transcript, &points, rng)
+                }
 
-                    let mut trans = Transcript::new(b"Benchmark");
-                    let (proof, points) = prove_batchable(&mut trans, ProveAssignments {
-                        $($secret_var: &assignments.$secret_var,)+
-                        $($instance_var: &assignments.$instance_var,)*
-                        $($common_var: &assignments.$common_var,)*
-                    });
+                $crate::__cfg_std! {
+                    /// Check the remaining proofs, and return the first error in the whole stream.
+                    pub fn finish(self) -> Result<(), ProofError> {
+                        self.finish_with_rng(&mut $crate::rand::thread_rng())
+                    }
+                }
+
+                /// Like [`StreamingVerifier::finish`], but draws the verifier's random factors from `rng`.
+                pub fn finish_with_rng<R: RngCore + CryptoRng>(self, rng: &mut R) -> Result<(), ProofError> {
+                    self.inner.finish_with_rng(rng)
+                }
+            }
+
+            pub fn output_latex_protocol() -> String {
+                "PoK$\\{(x) : A = g^x\\}$".to_string() //TODO: implement!
+            }
+
+            pub fn measure() -> usize {
+                5 //TODO: implement! I guess it's not gonna be usize as output, but some Measurement object
+            }
+
+            $crate::__cfg_bench! {
+                #[cfg(test)]
+                mod bench {
+                    use super::*;
+                    use $crate::rand::thread_rng;
+
+                    extern crate test;
+                    use self::test::Bencher;
+
+                    #[bench]
+                    fn prove(b: &mut Bencher) {
+                        let mut rng = thread_rng();
+
+                        struct RandomAssignments {
+                            pub witness: Witness,
+                            $(pub $instance_var: RistrettoPoint,)*
+                            $(pub $common_var: RistrettoPoint,)*
+                        }
+
+                        let assignments = RandomAssignments {
+                            witness: Witness::random(&mut rng),
+                            $($instance_var: RistrettoPoint::random(&mut rng),)*
+                            $($common_var: RistrettoPoint::random(&mut rng),)*
+                        };
+
+                        // Proving is constant time, so it shouldn't matter
+                        // that the relation is not satisfied by random assignments.
+                        b.iter(|| {
+                            let mut trans = Transcript::new(b"Benchmark");
+                            prove_compact(&mut trans, ProveAssignments {
+                                witness: &assignments.witness,
+                                $($instance_var: &assignments.$instance_var,)*
+                                $($common_var: &assignments.$common_var,)*
+                            })
+                        });
+                    }
+
+                    #[bench]
+                    fn verify_compact_proof(b: &mut Bencher) {
+                        let mut rng = thread_rng();
+
+                        struct RandomAssignments {
+                            pub witness: Witness,
+                            $(pub $instance_var: RistrettoPoint,)*
+                            $(pub $common_var: RistrettoPoint,)*
+                        }
+
+                        let assignments = RandomAssignments {
+                            witness: Witness::random(&mut rng),
+                            $($instance_var: RistrettoPoint::random(&mut rng),)*
+                            $($common_var: RistrettoPoint::random(&mut rng),)*
+                        };
 
-                    // The proof is well-formed but invalid, so the
-                    // batchable verification should perform the check and
-                    // see a non-identity point.  So verification failure
-                    // should not affect timing.
-                    b.iter(|| {
                         let mut trans = Transcript::new(b"Benchmark");
-                        verify_batchable(&proof, &mut trans, VerifyAssignments {
-                            $($instance_var: &points.$instance_var,)*
-                            $($common_var: &points.$common_var,)*
-                        })
-                    });
+                        let (proof, points) = prove_compact(&mut trans, ProveAssignments {
+                            witness: &assignments.witness,
+                            $($instance_var: &assignments.$instance_var,)*
+                            $($common_var: &assignments.$common_var,)*
+                        }).unwrap();
+
+                        // The proof is well-formed but invalid, so the
+                        // compact verification should fall through to the
+                        // final check on the recomputed challenge, and
+                        // therefore verification failure should not affect
+                        // timing.
+                        b.iter(|| {
+                            let mut trans = Transcript::new(b"Benchmark");
+                            verify_compact(&proof, &mut trans, VerifyAssignments {
+                                $($instance_var: &points.$instance_var,)*
+                                $($common_var: &points.$common_var,)*
+                            })
+                        });
+                    }
+
+                    #[bench]
+                    fn verify_batchable_proof(b: &mut Bencher) {
+                        let mut rng = thread_rng();
+
+                        struct RandomAssignments {
+                            pub witness: Witness,
+                            $(pub $instance_var: RistrettoPoint,)*
+                            $(pub $common_var: RistrettoPoint,)*
+                        }
+
+                        let assignments = RandomAssignments {
+                            witness: Witness::random(&mut rng),
+                            $($instance_var: RistrettoPoint::random(&mut rng),)*
+                            $($common_var: RistrettoPoint::random(&mut rng),)*
+                        };
+
+                        let mut trans = Transcript::new(b"Benchmark");
+                        let (proof, points) = prove_batchable(&mut trans, ProveAssignments {
+                            witness: &assignments.witness,
+                            $($instance_var: &assignments.$instance_var,)*
+                            $($common_var: &assignments.$common_var,)*
+                        }).unwrap();
+
+                        // The proof is well-formed but invalid, so the
+                        // batchable verification should perform the check and
+                        // see a non-identity point.  So verification failure
+                        // should not affect timing.
+                        b.iter(|| {
+                            let mut trans = Transcript::new(b"Benchmark");
+                            verify_batchable(&proof, &mut trans, VerifyAssignments {
+                                $($instance_var: &points.$instance_var,)*
+                                $($common_var: &points.$common_var,)*
+                            })
+                        });
+                    }
                 }
             }
         }
-    }
+    };
+    ($($rest:tt)*) => {
+        $crate::define_proof!{ @flags [test as_written] $($rest)* }
+    };
 }
@@ -1,3 +1,5 @@
+use alloc::vec::Vec;
+
 use curve25519_dalek::ristretto::CompressedRistretto;
 use curve25519_dalek::scalar::Scalar;
 
@@ -7,28 +9,34 @@ use curve25519_dalek::scalar::Scalar;
 /// challenge in place of the commitments to the prover's randomness.
 /// However, this optimization prevents batch verification.
 ///
-/// This proof has `m+1` 32-byte elements, where `m` is the number of
-/// secret variables.  This means there is no space savings for a
+/// This proof has `m+k` 32-byte elements, where `m` is the number of
+/// secret variables in each clause, added up, and `k` is the number
+/// of clauses.  This means there is no space savings for a
 /// `CompactProof` over a `BatchableProof` when there is only one
 /// statement.
 #[derive(Clone, Serialize, Deserialize)]
 pub struct CompactProof {
-    /// The Fiat-Shamir challenges.
+    /// The challenges of the clauses, which add up to the
+    /// Fiat-Shamir challenge.
     pub challenges: Vec<Scalar>,
-    /// The prover's responses, one per secret variable.
+    /// The prover's responses, one per secret variable of each
+    /// clause, ordered by clause and then by variable.
     pub responses: Vec<Scalar>,
 }
 
 /// A Schnorr proof in batchable format.
 ///
-/// This proof has `m+n` 32-byte elements, where `m` is the number of
-/// secret variables and `n` is the number of statements.
+/// This proof has `m+k+n` 32-byte elements, where `m` is the number
+/// of secret variables in each clause, added up, `k` is the number of
+/// clauses, and `n` is the number of statements.
 #[derive(Clone, Serialize, Deserialize, Default)]
 pub struct BatchableProof {
-    /// The Fiat-Shamir challenges.
+    /// The challenges of the clauses, which add up to the
+    /// Fiat-Shamir challenge.
     pub challenges: Vec<Scalar>,
     /// Commitments to the prover's blinding factors.
     pub commitments: Vec<CompressedRistretto>,
-    /// The prover's responses, one per secret variable.
+    /// The prover's responses, one per secret variable of each
+    /// clause, ordered by clause and then by variable.
     pub responses: Vec<Scalar>,
 }
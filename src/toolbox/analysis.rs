@@ -0,0 +1,213 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+
+use crate::errors::label_string;
+use crate::toolbox::{check_lhs, Clauses, SchnorrCS};
+
+/// Used to record a proof statement and check it for common mistakes.
+///
+/// To use a [`StatementAnalyzer`], first construct one using
+/// [`StatementAnalyzer::new()`].  Then, allocate secret
+/// ([`StatementAnalyzer::allocate_scalar`]) and public
+/// ([`StatementAnalyzer::allocate_point`]) variables, and use those
+/// variables to define the proof statement.  The assignments to the
+/// public variables are optional; without them, the checks which
+/// depend on the points are skipped.
+///
+/// Finally, use [`StatementAnalyzer::analyze`] to list the
+/// [`Issue`]s of the statement.  None of them make proofs fail, but
+/// they are usually mistakes in the statement, which may make it
+/// prove less than intended.
+#[derive(Default)]
+pub struct StatementAnalyzer {
+    scalar_labels: Vec<Vec<u8>>,
+    point_labels: Vec<Vec<u8>>,
+    point_assignments: Vec<Option<CompressedRistretto>>,
+    constraints: Vec<Constraint>,
+    clauses: Clauses,
+}
+
+/// A secret variable used during analysis.
+#[derive(Copy, Clone)]
+pub struct ScalarVar(usize);
+
+/// A public variable used during analysis.
+#[derive(Copy, Clone)]
+pub struct PointVar(usize);
+
+/// A constraint `lhs = linear_combination`, tagged with its clause number.
+type Constraint = (usize, Vec<(Scalar, PointVar)>, Vec<(ScalarVar, PointVar)>);
+
+/// A possible mistake in a proof statement, found by a
+/// [`StatementAnalyzer`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Issue {
+    /// The secret variable with the given label appears in no
+    /// constraint, so proofs don't show knowledge of it.
+    UnusedSecret {
+        /// The label of the secret variable.
+        label: String,
+    },
+    /// A constraint is the same as an earlier one of the same
+    /// clause, up to the order of its terms.
+    DuplicateConstraint {
+        /// The index of the constraint.
+        constraint: usize,
+        /// The index of the earlier constraint.
+        duplicate_of: usize,
+    },
+    /// The same point is in the LHS of constraints under two different
+    /// labels.
+    ReusedLhsPoint {
+        /// The label of the first variable with the point.
+        label: String,
+        /// The label of the other variable with the point.
+        other_label: String,
+    },
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Issue::UnusedSecret { label } => {
+                write!(f, "Secret variable {:?} appears in no constraint.", label)
+            }
+            Issue::DuplicateConstraint {
+                constraint,
+                duplicate_of,
+            } => write!(f, "Constraint {} duplicates constraint {}.", constraint, duplicate_of),
+            Issue::ReusedLhsPoint { label, other_label } => write!(
+                f,
+                "Point variables {:?} and {:?} are the same LHS point.",
+                label, other_label
+            ),
+        }
+    }
+}
+
+impl StatementAnalyzer {
+    /// Construct a new, empty, statement analyzer.
+    pub fn new() -> Self {
+        StatementAnalyzer::default()
+    }
+
+    /// Allocate a secret variable with the given `label`.
+    pub fn allocate_scalar(&mut self, label: &[u8]) -> ScalarVar {
+        self.scalar_labels.push(label.to_vec());
+        ScalarVar(self.scalar_labels.len() - 1)
+    }
+
+    /// Allocate a public variable with the given `label`, and
+    /// optionally its `assignment`.
+    pub fn allocate_point(
+        &mut self,
+        label: &[u8],
+        assignment: Option<CompressedRistretto>,
+    ) -> PointVar {
+        self.point_labels.push(label.to_vec());
+        self.point_assignments.push(assignment);
+        PointVar(self.point_labels.len() - 1)
+    }
+
+    /// List the issues of the recorded statement.
+    pub fn analyze(&self) -> Vec<Issue> {
+        let mut issues = Vec::new();
+
+        for (index, label) in self.scalar_labels.iter().enumerate() {
+            let used = self
+                .constraints
+                .iter()
+                .any(|(_clause_nr, _lhs_var, rhs_lc)| rhs_lc.iter().any(|(sc_var, _)| sc_var.0 == index));
+            if !used {
+                issues.push(Issue::UnusedSecret {
+                    label: label_string(label),
+                });
+            }
+        }
+
+        let terms = self
+            .constraints
+            .iter()
+            .map(|(_clause_nr, lhs, rhs_lc)| {
+                let mut lhs_terms = lhs
+                    .iter()
+                    .map(|(coeff, pt_var)| (pt_var.0, coeff.to_bytes()))
+                    .collect::<Vec<_>>();
+                lhs_terms.sort_unstable();
+                let mut terms = rhs_lc
+                    .iter()
+                    .map(|(sc_var, pt_var)| (sc_var.0, pt_var.0))
+                    .collect::<Vec<_>>();
+                terms.sort_unstable();
+                (lhs_terms, terms)
+            })
+            .collect::<Vec<_>>();
+        for (constraint, (clause_nr, _lhs, _rhs_lc)) in self.constraints.iter().enumerate() {
+            let duplicate_of = self.constraints[..constraint].iter().zip(terms.iter()).position(
+                |((other_clause_nr, _other_lhs, _rhs_lc), other_terms)| {
+                    other_clause_nr == clause_nr && *other_terms == terms[constraint]
+                },
+            );
+            if let Some(duplicate_of) = duplicate_of {
+                issues.push(Issue::DuplicateConstraint {
+                    constraint,
+                    duplicate_of,
+                });
+            }
+        }
+
+        let mut lhs_vars = Vec::<usize>::new();
+        let lhs_points = self
+            .constraints
+            .iter()
+            .flat_map(|(_clause_nr, lhs, _rhs_lc)| lhs.iter().map(|(_coeff, pt_var)| pt_var));
+        for lhs_var in lhs_points {
+            let point = match self.point_assignments[lhs_var.0] {
+                Some(point) => point,
+                None => continue,
+            };
+            let label = &self.point_labels[lhs_var.0];
+            let reused = lhs_vars.iter().find(|var| {
+                self.point_assignments[**var] == Some(point) && self.point_labels[**var] != *label
+            });
+            if let Some(var) = reused {
+                let issue = Issue::ReusedLhsPoint {
+                    label: label_string(&self.point_labels[*var]),
+                    other_label: label_string(label),
+                };
+                if !issues.contains(&issue) {
+                    issues.push(issue);
+                }
+            }
+            lhs_vars.push(lhs_var.0);
+        }
+
+        issues
+    }
+}
+
+impl SchnorrCS for StatementAnalyzer {
+    type ScalarVar = ScalarVar;
+    type PointVar = PointVar;
+    type SubroutineVar = StatementAnalyzer;
+
+    fn constrain_combination(
+        &mut self,
+        lhs: Vec<(Scalar, PointVar)>,
+        linear_combination: Vec<(ScalarVar, PointVar)>,
+    ) {
+        check_lhs(&lhs);
+        let clause_nr = self.clauses.next_constraint();
+        self.constraints.push((clause_nr, lhs, linear_combination));
+    }
+
+    fn add_subroutine(&mut self, _subroutine: StatementAnalyzer) {}
+
+    fn clauses(&mut self) -> &mut Clauses {
+        &mut self.clauses
+    }
+}
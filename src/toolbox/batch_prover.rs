@@ -0,0 +1,449 @@
+#[cfg(feature = "std")]
+use rand::rngs::StdRng;
+#[cfg(feature = "std")]
+use rand::{thread_rng, SeedableRng};
+use rand::{CryptoRng, RngCore};
+
+use alloc::vec::Vec;
+use core::mem;
+#[cfg(feature = "std")]
+use std::{panic, thread};
+
+use zeroize::Zeroize;
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoBasepointTable, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+
+#[cfg(feature = "std")]
+use crate::toolbox::batch_verifier::parallel_chunk_size;
+use crate::toolbox::prover::{Constraint, PointVar, Prover, ScalarVar};
+use crate::toolbox::{check_lhs, normalize_constraints, Clauses, SchnorrCS, TranscriptProtocol};
+use crate::{BatchableProof, CompactProof, ProofError, Transcript};
+
+/// Used to create many proofs of one statement at once.
+///
+/// To use a [`BatchProver`], first construct one using
+/// [`BatchProver::new()`], declaring a batch size, supplying a domain
+/// separation label for the proof statement, as well as a transcript
+/// for each proof to create.
+///
+/// Allocate secret variables using [`BatchProver::allocate_scalar`],
+/// with one assignment per proof.
+///
+/// To allocate points which have the same assignment for all proofs
+/// in the batch, use [`BatchProver::allocate_static_point`] or
+/// [`BatchProver::allocate_static_point_with_table`].  These points
+/// are compressed only once for the whole batch.
+///
+/// To allocate points which have different assignments for each
+/// proof instance, use [`BatchProver::allocate_instance_point`], or
+/// use [`BatchProver::allocate_point`] to let the prover share the
+/// work for points which turn out to be the same in every instance.
+///
+/// The proof statement is then defined once for the whole batch.
+/// Finally, use [`BatchProver::prove_batchable`] or
+/// [`BatchProver::prove_compact`] to consume the prover and produce
+/// one proof per transcript, or
+/// [`BatchProver::prove_batchable_parallel`] and
+/// [`BatchProver::prove_compact_parallel`] to spread the proofs over
+/// several threads.  The proofs are the same as those a
+/// [`Prover`] would produce for each instance, but the prover reuses
+/// the same buffers for the assignments of every instance it proves.
+///
+/// The secret assignments held by the prover are zeroized when it is
+/// dropped.
+pub struct BatchProver<'a> {
+    batch_size: usize,
+    transcripts: Vec<&'a mut Transcript>,
+
+    /// The assignments to each secret variable, one per proof.
+    scalars: Vec<Vec<Option<Scalar>>>,
+    points: Vec<PointAssignment>,
+    point_tables: Vec<Option<&'a RistrettoBasepointTable>>,
+    point_labels: Vec<&'a [u8]>,
+    constraints: Vec<Constraint>,
+    clauses: Clauses,
+    /// The clause to prove in each proof, if set.
+    real_clauses: Vec<usize>,
+}
+
+/// The assignment to a point variable of a [`BatchProver`].
+enum PointAssignment {
+    Static(RistrettoPoint),
+    Instance(Vec<RistrettoPoint>),
+}
+
+impl<'a> BatchProver<'a> {
+    /// Construct a new batch prover for the statement with the given
+    /// `proof_label`.
+    ///
+    /// The `batch_size` is required as an up-front parameter to help
+    /// prevent errors with size mismatches.
+    pub fn new(
+        proof_label: &[u8],
+        batch_size: usize,
+        mut transcripts: Vec<&'a mut Transcript>,
+    ) -> Result<Self, ProofError> {
+        if transcripts.len() != batch_size {
+            return Err(ProofError::BatchSizeMismatch);
+        }
+        for transcript in transcripts.iter_mut() {
+            transcript.domain_sep(proof_label);
+        }
+        Ok(BatchProver {
+            batch_size,
+            transcripts,
+            scalars: Vec::default(),
+            points: Vec::default(),
+            point_tables: Vec::default(),
+            point_labels: Vec::default(),
+            constraints: Vec::default(),
+            clauses: Clauses::default(),
+            real_clauses: Vec::default(),
+        })
+    }
+
+    /// Allocate a secret variable with the given `label`, with one
+    /// assignment per proof.
+    pub fn allocate_scalar(
+        &mut self,
+        label: &[u8],
+        assignments: Vec<Option<Scalar>>,
+    ) -> Result<ScalarVar, ProofError> {
+        if assignments.len() != self.batch_size {
+            return Err(ProofError::BatchSizeMismatch);
+        }
+        for transcript in self.transcripts.iter_mut() {
+            transcript.append_scalar_var(label);
+        }
+        self.scalars.push(assignments);
+        Ok(ScalarVar(self.scalars.len() - 1))
+    }
+
+    /// Set the clause to prove in each proof, counting from 1.
+    ///
+    /// See [`Prover::set_real_clause`] for details.
+    pub fn set_real_clauses(&mut self, clause_nrs: Vec<usize>) -> Result<(), ProofError> {
+        if clause_nrs.len() != self.batch_size {
+            return Err(ProofError::BatchSizeMismatch);
+        }
+        self.real_clauses = clause_nrs;
+        Ok(())
+    }
+
+    /// Allocate a point variable whose assignment is common to all
+    /// proofs in the batch.
+    ///
+    /// The point is compressed once, and the compressed point is
+    /// returned to allow reusing the result of that computation.
+    pub fn allocate_static_point(
+        &mut self,
+        label: &'a [u8],
+        assignment: RistrettoPoint,
+    ) -> (PointVar, CompressedRistretto) {
+        self.push_static_point(label, assignment, None)
+    }
+
+    /// Allocate a point variable whose assignment is common to all
+    /// proofs in the batch and is the basepoint of a precomputed
+    /// `table`, which is used for multiplications by it.
+    pub fn allocate_static_point_with_table(
+        &mut self,
+        label: &'a [u8],
+        table: &'a RistrettoBasepointTable,
+    ) -> (PointVar, CompressedRistretto) {
+        self.push_static_point(label, table.basepoint(), Some(table))
+    }
+
+    fn push_static_point(
+        &mut self,
+        label: &'a [u8],
+        assignment: RistrettoPoint,
+        table: Option<&'a RistrettoBasepointTable>,
+    ) -> (PointVar, CompressedRistretto) {
+        let compressed = assignment.compress();
+        for transcript in self.transcripts.iter_mut() {
+            transcript.append_compressed_point_var(label, &compressed);
+        }
+        self.points.push(PointAssignment::Static(assignment));
+        self.point_tables.push(table);
+        self.point_labels.push(label);
+        (PointVar(self.points.len() - 1), compressed)
+    }
+
+    /// Allocate a point variable with a different assignment for each
+    /// proof instance.
+    ///
+    /// The compressed points are returned to allow reusing the result
+    /// of that computation.
+    pub fn allocate_instance_point(
+        &mut self,
+        label: &'a [u8],
+        assignments: Vec<RistrettoPoint>,
+    ) -> Result<(PointVar, Vec<CompressedRistretto>), ProofError> {
+        if assignments.len() != self.batch_size {
+            return Err(ProofError::BatchSizeMismatch);
+        }
+        let compressed = self
+            .transcripts
+            .iter_mut()
+            .zip(assignments.iter())
+            .map(|(transcript, assignment)| transcript.append_point_var(label, assignment))
+            .collect();
+        self.points.push(PointAssignment::Instance(assignments));
+        self.point_tables.push(None);
+        self.point_labels.push(label);
+        Ok((PointVar(self.points.len() - 1), compressed))
+    }
+
+    /// Allocate a point variable with one assignment per proof
+    /// instance, as a static point if all the assignments are the
+    /// same, and as an instance point otherwise.
+    ///
+    /// The transcripts are the same either way, and the compressed
+    /// points are returned to allow reusing the result of that
+    /// computation.
+    pub fn allocate_point(
+        &mut self,
+        label: &'a [u8],
+        assignments: Vec<RistrettoPoint>,
+    ) -> Result<(PointVar, Vec<CompressedRistretto>), ProofError> {
+        if assignments.len() != self.batch_size {
+            return Err(ProofError::BatchSizeMismatch);
+        }
+        match assignments.first() {
+            Some(first) if assignments.iter().all(|assignment| assignment == first) => {
+                let (var, compressed) = self.allocate_static_point(label, *first);
+                Ok((var, vec![compressed; self.batch_size]))
+            }
+            _ => self.allocate_instance_point(label, assignments),
+        }
+    }
+
+    /// Normalize the statement, removing duplicate constraints and
+    /// ordering the terms of each constraint.
+    ///
+    /// See [`Prover::normalize`] for details.
+    pub fn normalize(&mut self) {
+        normalize_constraints(&mut self.constraints);
+    }
+
+    /// Consume this prover to produce one compact proof per transcript.
+    #[cfg(feature = "std")]
+    pub fn prove_compact(self) -> Result<Vec<CompactProof>, ProofError> {
+        self.prove_compact_with_rng(&mut thread_rng())
+    }
+
+    /// Consume this prover to produce one compact proof per
+    /// transcript, using `rng` to hedge the generation of the
+    /// blinding factors.
+    pub fn prove_compact_with_rng<R: RngCore + CryptoRng>(
+        self,
+        rng: &mut R,
+    ) -> Result<Vec<CompactProof>, ProofError> {
+        Ok(self
+            .prove_batchable_with_rng(rng)?
+            .into_iter()
+            .map(|proof| CompactProof {
+                challenges: proof.challenges,
+                responses: proof.responses,
+            })
+            .collect())
+    }
+
+    /// Consume this prover to produce one batchable proof per transcript.
+    #[cfg(feature = "std")]
+    pub fn prove_batchable(self) -> Result<Vec<BatchableProof>, ProofError> {
+        self.prove_batchable_with_rng(&mut thread_rng())
+    }
+
+    /// Consume this prover to produce one batchable proof per
+    /// transcript, using `rng` to hedge the generation of the
+    /// blinding factors.
+    ///
+    /// See [`Prover::prove_compact_with_rng`] for details.
+    pub fn prove_batchable_with_rng<R: RngCore + CryptoRng>(
+        mut self,
+        rng: &mut R,
+    ) -> Result<Vec<BatchableProof>, ProofError> {
+        let mut transcripts = mem::take(&mut self.transcripts);
+
+        self.prove_instances(0, &mut transcripts, rng).into_iter().collect()
+    }
+
+    /// Consume this prover to produce one compact proof per
+    /// transcript, using up to `num_threads` threads.
+    #[cfg(feature = "std")]
+    pub fn prove_compact_parallel(self, num_threads: usize) -> Result<Vec<CompactProof>, ProofError> {
+        self.prove_compact_parallel_with_rng(num_threads, &mut thread_rng())
+    }
+
+    /// Like [`BatchProver::prove_compact_parallel`], but generates the
+    /// blinding factors with randomness from `rng`.
+    ///
+    /// See [`BatchProver::prove_batchable_parallel_with_rng`] for details.
+    #[cfg(feature = "std")]
+    pub fn prove_compact_parallel_with_rng<R: RngCore + CryptoRng>(
+        self,
+        num_threads: usize,
+        rng: &mut R,
+    ) -> Result<Vec<CompactProof>, ProofError> {
+        Ok(self
+            .prove_batchable_parallel_with_rng(num_threads, rng)?
+            .into_iter()
+            .map(|proof| CompactProof {
+                challenges: proof.challenges,
+                responses: proof.responses,
+            })
+            .collect())
+    }
+
+    /// Consume this prover to produce one batchable proof per
+    /// transcript, using up to `num_threads` threads.
+    #[cfg(feature = "std")]
+    pub fn prove_batchable_parallel(self, num_threads: usize) -> Result<Vec<BatchableProof>, ProofError> {
+        self.prove_batchable_parallel_with_rng(num_threads, &mut thread_rng())
+    }
+
+    /// Like [`BatchProver::prove_batchable_parallel`], but generates
+    /// the blinding factors with randomness from `rng`.
+    ///
+    /// Each thread draws on its own [`StdRng`], seeded from `rng` in
+    /// the order of the chunks of proofs before the threads start, so
+    /// given the same `rng` and `num_threads`, the proofs are the same.
+    #[cfg(feature = "std")]
+    pub fn prove_batchable_parallel_with_rng<R: RngCore + CryptoRng>(
+        mut self,
+        num_threads: usize,
+        rng: &mut R,
+    ) -> Result<Vec<BatchableProof>, ProofError> {
+        let mut transcripts = mem::take(&mut self.transcripts);
+        let chunk_size = parallel_chunk_size(self.batch_size, num_threads);
+        let this = &self;
+
+        thread::scope(|scope| {
+            let handles = transcripts
+                .chunks_mut(chunk_size)
+                .enumerate()
+                .map(|(n, transcripts)| {
+                    let mut seed = <StdRng as SeedableRng>::Seed::default();
+                    rng.fill_bytes(seed.as_mut());
+                    let mut chunk_rng = StdRng::from_seed(seed);
+                    seed.zeroize();
+                    scope.spawn(move || this.prove_instances(n * chunk_size, transcripts, &mut chunk_rng))
+                })
+                .collect::<Vec<_>>();
+
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap_or_else(|e| panic::resume_unwind(e)))
+                .collect()
+        })
+    }
+
+    /// Produce the proofs for the instances starting at `first`, one
+    /// per transcript, sharing the constraints and the precomputed
+    /// tables of the batch, and reusing the same assignment buffers.
+    fn prove_instances<R: RngCore + CryptoRng>(
+        &self,
+        first: usize,
+        transcripts: &mut [&'a mut Transcript],
+        rng: &mut R,
+    ) -> Vec<Result<BatchableProof, ProofError>> {
+        let mut buffers = InstanceBuffers::new(self);
+
+        transcripts
+            .iter_mut()
+            .enumerate()
+            .map(|(k, transcript)| {
+                let j = first + k;
+                buffers.assign(self, j);
+                let mut prover = Prover::from_parts(
+                    transcript,
+                    &buffers.scalars,
+                    &buffers.points,
+                    &self.point_tables,
+                    &self.point_labels,
+                    &self.constraints,
+                );
+                if let Some(clause_nr) = self.real_clauses.get(j) {
+                    prover.set_real_clause(*clause_nr);
+                }
+                prover.prove_batchable_with_rng(rng).map_err(|e| e.in_batch(j))
+            })
+            .collect()
+    }
+}
+
+/// The assignments of one proof instance of a [`BatchProver`],
+/// reused for each instance proved by the same thread.
+struct InstanceBuffers {
+    scalars: Vec<Option<Scalar>>,
+    points: Vec<RistrettoPoint>,
+}
+
+impl InstanceBuffers {
+    /// Allocate the buffers, filling in the static points once.
+    fn new(prover: &BatchProver) -> Self {
+        InstanceBuffers {
+            scalars: vec![None; prover.scalars.len()],
+            points: prover
+                .points
+                .iter()
+                .map(|assignment| match assignment {
+                    PointAssignment::Static(point) => *point,
+                    PointAssignment::Instance(_) => RistrettoPoint::identity(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Fill in the assignments of the `j`-th proof instance.
+    fn assign(&mut self, prover: &BatchProver, j: usize) {
+        for (scalar, assignments) in self.scalars.iter_mut().zip(prover.scalars.iter()) {
+            *scalar = assignments[j];
+        }
+        for (point, assignment) in self.points.iter_mut().zip(prover.points.iter()) {
+            if let PointAssignment::Instance(points) = assignment {
+                *point = points[j];
+            }
+        }
+    }
+}
+
+impl Drop for InstanceBuffers {
+    fn drop(&mut self) {
+        self.scalars.zeroize();
+    }
+}
+
+impl<'a> Drop for BatchProver<'a> {
+    fn drop(&mut self) {
+        for assignments in self.scalars.iter_mut() {
+            assignments.zeroize();
+        }
+    }
+}
+
+impl<'a> SchnorrCS for BatchProver<'a> {
+    type ScalarVar = ScalarVar;
+    type PointVar = PointVar;
+    type SubroutineVar = BatchProver<'a>;
+
+    fn constrain_combination(
+        &mut self,
+        lhs: Vec<(Scalar, PointVar)>,
+        linear_combination: Vec<(ScalarVar, PointVar)>,
+    ) {
+        check_lhs(&lhs);
+        let clause_nr = self.clauses.next_constraint();
+        self.constraints.push((clause_nr, lhs, linear_combination));
+    }
+
+    fn add_subroutine(&mut self, _subroutine: BatchProver<'a>) {}
+
+    fn clauses(&mut self) -> &mut Clauses {
+        &mut self.clauses
+    }
+}
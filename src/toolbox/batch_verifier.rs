@@ -1,11 +1,22 @@
-use rand::{thread_rng, Rng};
+#[cfg(feature = "std")]
+use rand::thread_rng;
+use rand::{CryptoRng, Rng, RngCore};
+
+use alloc::vec::Vec;
+use core::mem;
+#[cfg(feature = "std")]
+use std::{panic, thread};
 
 use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
 use curve25519_dalek::scalar::Scalar;
+#[cfg(feature = "std")]
+use curve25519_dalek::traits::Identity;
 use curve25519_dalek::traits::{IsIdentity, VartimeMultiscalarMul};
 
-use crate::toolbox::{SchnorrCS, TranscriptProtocol};
+use crate::toolbox::shamir_secrets::SecretShare;
+use crate::toolbox::{check_lhs, normalize_constraints, Clauses, ProofLayout, SchnorrCS, TranscriptProtocol};
 use crate::util::Matrix;
+use crate::errors::label_string;
 use crate::{BatchableProof, ProofError, Transcript};
 
 /// Used to produce batch verification results.
@@ -26,7 +37,11 @@ use crate::{BatchableProof, ProofError, Transcript};
 /// proof instance, use [`BatchVerifier::allocate_instance_point`].
 ///
 /// Finally, use [`BatchVerifier::verify_batchable`] to consume the
-/// verifier and produce a batch verification result.
+/// verifier and produce a batch verification result, or
+/// [`BatchVerifier::find_invalid_proofs`] to also identify the
+/// invalid proofs if the batch fails.  For large batches,
+/// [`BatchVerifier::verify_batchable_parallel`] spreads the work
+/// over several threads.
 pub struct BatchVerifier<'a> {
     batch_size: usize,
     transcripts: Vec<&'a mut Transcript>,
@@ -34,21 +49,25 @@ pub struct BatchVerifier<'a> {
     num_scalars: usize,
 
     static_points: Vec<CompressedRistretto>,
-    static_point_labels: Vec<&'static [u8]>,
+    static_point_labels: Vec<&'a [u8]>,
 
     instance_points: Vec<Vec<CompressedRistretto>>,
-    instance_point_labels: Vec<&'static [u8]>,
+    instance_point_labels: Vec<&'a [u8]>,
 
-    constraints: Vec<(PointVar, Vec<(ScalarVar, PointVar)>)>,
+    constraints: Vec<Constraint>,
+    clauses: Clauses,
     subroutines: Vec<BatchVerifier<'a>>,
+
+    /// The first error found in each proof instance's assignments.
+    proof_errors: Vec<Option<ProofError>>,
 }
 
 /// A scalar variable used in batch verification.
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
 pub struct ScalarVar(usize);
 
 /// A point variable used in batch verification.
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
 pub enum PointVar {
     /// A variable whose assignment is common to all proofs in the batch.
     Static(usize),
@@ -56,6 +75,9 @@ pub enum PointVar {
     Instance(usize),
 }
 
+/// A constraint `lhs = linear_combination`, tagged with its clause number.
+type Constraint = (usize, Vec<(Scalar, PointVar)>, Vec<(ScalarVar, PointVar)>);
+
 impl<'a> BatchVerifier<'a> {
     /// Construct a new batch verifier for the statement with the
     /// given `proof_label`.
@@ -66,15 +88,15 @@ impl<'a> BatchVerifier<'a> {
     /// Note that this function requires one transcript borrow per
     /// proof.
     pub fn new(
-        proof_label: &'static [u8],
+        proof_label: &[u8],
         batch_size: usize,
         mut transcripts: Vec<&'a mut Transcript>,
     ) -> Result<Self, ProofError> {
         if transcripts.len() != batch_size {
             return Err(ProofError::BatchSizeMismatch);
         }
-        for i in 0..transcripts.len() {
-            transcripts[i].domain_sep(proof_label);
+        for transcript in transcripts.iter_mut() {
+            transcript.domain_sep(proof_label);
         }
         Ok(BatchVerifier {
             batch_size,
@@ -85,12 +107,14 @@ impl<'a> BatchVerifier<'a> {
             instance_points: Vec::default(),
             instance_point_labels: Vec::default(),
             constraints: Vec::default(),
+            clauses: Clauses::default(),
             subroutines: Vec::default(),
+            proof_errors: (0..batch_size).map(|_| None).collect(),
         })
     }
 
     /// Allocate a placeholder scalar variable with the given `label`.
-    pub fn allocate_scalar(&mut self, label: &'static [u8]) -> ScalarVar {
+    pub fn allocate_scalar(&mut self, label: &[u8]) -> ScalarVar {
         for transcript in self.transcripts.iter_mut() {
             transcript.append_scalar_var(label);
         }
@@ -101,7 +125,7 @@ impl<'a> BatchVerifier<'a> {
     /// Allocate a point variable whose assignment is common to all proofs in the batch.
     pub fn allocate_static_point(
         &mut self,
-        label: &'static [u8],
+        label: &'a [u8],
         assignment: CompressedRistretto,
     ) -> Result<PointVar, ProofError> {
         for transcript in self.transcripts.iter_mut() {
@@ -114,9 +138,12 @@ impl<'a> BatchVerifier<'a> {
     }
 
     /// Allocate a point variable with a different assignment for each proof instance.
+    ///
+    /// An invalid assignment for one proof instance doesn't cause an
+    /// error here; instead, that proof fails verification.
     pub fn allocate_instance_point(
         &mut self,
-        label: &'static [u8],
+        label: &'a [u8],
         assignments: Vec<CompressedRistretto>,
     ) -> Result<PointVar, ProofError> {
         if assignments.len() != self.batch_size {
@@ -125,8 +152,12 @@ impl<'a> BatchVerifier<'a> {
         // nll
         {
             let it = Iterator::zip(self.transcripts.iter_mut(), assignments.iter());
-            for (transcript, assignment) in it {
-                transcript.validate_and_append_point_var(label, &assignment)?;
+            for (j, (transcript, assignment)) in it.enumerate() {
+                if let Err(e) = transcript.validate_and_append_point_var(label, assignment) {
+                    if self.proof_errors[j].is_none() {
+                        self.proof_errors[j] = Some(e);
+                    }
+                }
             }
         }
         self.instance_points.push(assignments);
@@ -135,118 +166,497 @@ impl<'a> BatchVerifier<'a> {
         Ok(PointVar::Instance(self.instance_points.len() - 1))
     }
 
+    /// Normalize the statement, removing duplicate constraints and
+    /// ordering the terms of each constraint, as the prover did with
+    /// [`Prover::normalize`](crate::toolbox::prover::Prover::normalize).
+    ///
+    /// Terms with static points are ordered first.
+    pub fn normalize(&mut self) {
+        normalize_constraints(&mut self.constraints);
+    }
+
+    /// The layout of the challenges and responses of each proof.
+    fn layout(&self) -> ProofLayout {
+        ProofLayout::new(self.constraints.iter().map(|(clause_nr, _lhs_var, rhs_lc)| {
+            (*clause_nr, rhs_lc.iter().map(|(sc_var, _pt_var)| sc_var.0))
+        }))
+    }
+
     /// Consume the verifier to produce a verification result.
-    pub fn verify_batchable(mut self, proofs: &[BatchableProof]) -> Result<(), ProofError> {
+    #[cfg(feature = "std")]
+    pub fn verify_batchable(self, proofs: &[BatchableProof]) -> Result<(), ProofError> {
+        self.verify_batchable_with_rng(proofs, &mut thread_rng())
+    }
+
+    /// Consume the verifier to produce a verification result, drawing
+    /// the random factors used to combine the verification equations
+    /// from `rng`.
+    pub fn verify_batchable_with_rng<R: RngCore + CryptoRng>(
+        mut self,
+        proofs: &[BatchableProof],
+        rng: &mut R,
+    ) -> Result<(), ProofError> {
         if proofs.len() != self.batch_size {
             return Err(ProofError::BatchSizeMismatch);
         }
 
-        for proof in proofs {
-            if proof.commitments.len() != self.constraints.len() {
-                return Err(ProofError::VerificationFailure);
-            }
-            if proof.responses.len() != self.num_scalars {
-                return Err(ProofError::VerificationFailure);
+        let batch = self
+            .compute_challenges(proofs)
+            .into_iter()
+            .enumerate()
+            .map(|(j, checked)| checked.map(|()| j).map_err(|e| e.in_batch(j)))
+            .collect::<Result<Vec<_>, ProofError>>()?;
+
+        self.check_batch(proofs, &batch, rng)
+    }
+
+    /// Consume the verifier to produce a verification result, using
+    /// up to `num_threads` threads.
+    ///
+    /// The result is the same as that of [`BatchVerifier::verify_batchable`].
+    #[cfg(feature = "std")]
+    pub fn verify_batchable_parallel(
+        self,
+        proofs: &[BatchableProof],
+        num_threads: usize,
+    ) -> Result<(), ProofError> {
+        self.verify_batchable_parallel_with_rng(proofs, num_threads, &mut thread_rng())
+    }
+
+    /// Like [`BatchVerifier::verify_batchable_parallel`], but draws the
+    /// random factors used to combine the verification equations from
+    /// `rng`.
+    ///
+    /// The transcripts of the proofs are processed in chunks on
+    /// separate threads, and the combined verification check is split
+    /// into one multiscalar multiplication per chunk, whose results
+    /// are added together.  The random factors are drawn from `rng` in
+    /// the same order as by [`BatchVerifier::verify_batchable_with_rng`],
+    /// so given the same `rng`, both compute the same check.
+    #[cfg(feature = "std")]
+    pub fn verify_batchable_parallel_with_rng<R: RngCore + CryptoRng>(
+        mut self,
+        proofs: &[BatchableProof],
+        num_threads: usize,
+        rng: &mut R,
+    ) -> Result<(), ProofError> {
+        if proofs.len() != self.batch_size {
+            return Err(ProofError::BatchSizeMismatch);
+        }
+
+        let batch = self
+            .compute_challenges_parallel(proofs, num_threads)
+            .into_iter()
+            .enumerate()
+            .map(|(j, checked)| checked.map(|()| j).map_err(|e| e.in_batch(j)))
+            .collect::<Result<Vec<_>, ProofError>>()?;
+
+        self.check_batch_parallel(proofs, &batch, num_threads, rng)
+    }
+
+    /// Consume the verifier to verify the batch, and identify which
+    /// proofs are invalid if the batch fails verification.
+    ///
+    /// Returns the indices of the invalid proofs in increasing order,
+    /// so an empty list means that every proof in the batch is valid.
+    #[cfg(feature = "std")]
+    pub fn find_invalid_proofs(self, proofs: &[BatchableProof]) -> Result<Vec<usize>, ProofError> {
+        self.find_invalid_proofs_with_rng(proofs, &mut thread_rng())
+    }
+
+    /// Like [`BatchVerifier::find_invalid_proofs`], but draws the
+    /// random factors used to combine the verification equations from
+    /// `rng`.
+    ///
+    /// If the whole batch fails verification, it is split in halves
+    /// which are verified separately, recursively, until the invalid
+    /// proofs are isolated.  This costs a few extra batch checks per
+    /// invalid proof, rather than one check per proof in the batch.
+    ///
+    /// An invalid static point is reported as an error rather than
+    /// an invalid proof, as it makes every proof fail.
+    pub fn find_invalid_proofs_with_rng<R: RngCore + CryptoRng>(
+        mut self,
+        proofs: &[BatchableProof],
+        rng: &mut R,
+    ) -> Result<Vec<usize>, ProofError> {
+        if proofs.len() != self.batch_size {
+            return Err(ProofError::BatchSizeMismatch);
+        }
+        self.check_static_points()?;
+
+        // Malformed proofs can be identified without a batch check
+        let mut invalid = Vec::new();
+        let mut batch = Vec::with_capacity(self.batch_size);
+        for (j, checked) in self.compute_challenges(proofs).into_iter().enumerate() {
+            match checked {
+                Ok(()) => batch.push(j),
+                Err(_) => invalid.push(j),
             }
         }
 
-        // Feed each prover's commitments into their respective transcript
-        for j in 0..self.batch_size {
-            for (i, com) in proofs[j].commitments.iter().enumerate() {
-                let label = match self.constraints[i].0 {
-                    PointVar::Static(var_idx) => self.static_point_labels[var_idx],
-                    PointVar::Instance(var_idx) => self.instance_point_labels[var_idx],
-                };
-                self.transcripts[j].validate_and_append_blinding_commitment(label, &com)?;
+        self.bisect(proofs, &batch, rng, &mut invalid);
+        invalid.sort_unstable();
+        Ok(invalid)
+    }
+
+    /// Recursively split the `batch` until every invalid proof in it
+    /// is isolated and added to `invalid`.
+    fn bisect<R: RngCore + CryptoRng>(
+        &self,
+        proofs: &[BatchableProof],
+        batch: &[usize],
+        rng: &mut R,
+        invalid: &mut Vec<usize>,
+    ) {
+        if batch.is_empty() || self.check_batch(proofs, batch, rng).is_ok() {
+            return;
+        }
+        if batch.len() == 1 {
+            invalid.push(batch[0]);
+            return;
+        }
+        let (left, right) = batch.split_at(batch.len() / 2);
+        self.bisect(proofs, left, rng, invalid);
+        self.bisect(proofs, right, rng, invalid);
+    }
+
+    /// Feed each prover's commitments into their respective
+    /// transcript, and check the challenges of each proof against
+    /// it, or find the reason why the proof is malformed.
+    fn compute_challenges(&mut self, proofs: &[BatchableProof]) -> Vec<Result<(), ProofError>> {
+        let mut transcripts = mem::take(&mut self.transcripts);
+        let mut proof_errors = mem::take(&mut self.proof_errors);
+
+        self.compute_challenges_in(proofs, &mut transcripts, &mut proof_errors)
+    }
+
+    /// Like [`BatchVerifier::compute_challenges`], but processes
+    /// chunks of the proofs on up to `num_threads` threads.
+    #[cfg(feature = "std")]
+    fn compute_challenges_parallel(
+        &mut self,
+        proofs: &[BatchableProof],
+        num_threads: usize,
+    ) -> Vec<Result<(), ProofError>> {
+        let mut transcripts = mem::take(&mut self.transcripts);
+        let mut proof_errors = mem::take(&mut self.proof_errors);
+        let chunk_size = parallel_chunk_size(proofs.len(), num_threads);
+        let this = &*self;
+
+        thread::scope(|scope| {
+            let handles = proofs
+                .chunks(chunk_size)
+                .zip(transcripts.chunks_mut(chunk_size))
+                .zip(proof_errors.chunks_mut(chunk_size))
+                .map(|((proofs, transcripts), proof_errors)| {
+                    scope.spawn(move || this.compute_challenges_in(proofs, transcripts, proof_errors))
+                })
+                .collect::<Vec<_>>();
+
+            handles.into_iter().flat_map(join_thread).collect()
+        })
+    }
+
+    fn compute_challenges_in(
+        &self,
+        proofs: &[BatchableProof],
+        transcripts: &mut [&mut Transcript],
+        proof_errors: &mut [Option<ProofError>],
+    ) -> Vec<Result<(), ProofError>> {
+        let num_c = self.constraints.len();
+        let layout = self.layout();
+
+        proofs
+            .iter()
+            .zip(transcripts.iter_mut())
+            .zip(proof_errors.iter_mut())
+            .map(|((proof, transcript), proof_error)| {
+                if let Some(e) = proof_error.take() {
+                    return Err(e);
+                }
+                if proof.commitments.len() != num_c {
+                    return Err(ProofError::WrongCommitmentCount {
+                        expected: num_c,
+                        found: proof.commitments.len(),
+                    });
+                }
+                layout.check_sizes(&proof.challenges, &proof.responses)?;
+                for (i, com) in proof.commitments.iter().enumerate() {
+                    let label = match self.constraints[i].1[0].1 {
+                        PointVar::Static(var_idx) => self.static_point_labels[var_idx],
+                        PointVar::Instance(var_idx) => self.instance_point_labels[var_idx],
+                    };
+                    transcript
+                        .validate_and_append_blinding_commitment(label, com)
+                        .map_err(|_| ProofError::InvalidCommitment { constraint: i })?;
+                }
+                let challenge = transcript.get_challenge(b"chal");
+                if challenge != SecretShare::reconstruct(&proof.challenges) {
+                    return Err(ProofError::ChallengeMismatch);
+                }
+                Ok(())
+            })
+            .collect()
+    }
+
+    /// Perform the combined verification check for the proofs in
+    /// `batch`, given by their indices.
+    fn check_batch<R: RngCore + CryptoRng>(
+        &self,
+        proofs: &[BatchableProof],
+        batch: &[usize],
+        rng: &mut R,
+    ) -> Result<(), ProofError> {
+        let random_factors = self.random_factors(batch.len(), rng);
+
+        let mut static_coeffs = vec![Scalar::zero(); self.static_points.len()];
+        let (instance_coeffs, instance_points) =
+            self.batch_terms(proofs, batch, &random_factors, 0, &mut static_coeffs);
+
+        let check = RistrettoPoint::optional_multiscalar_mul(
+            static_coeffs
+                .iter()
+                .chain(instance_coeffs.row_major_entries()),
+            self.static_points
+                .iter()
+                .chain(instance_points.iter())
+                .map(|pt| pt.decompress()),
+        )
+        .ok_or_else(|| self.decompression_error(proofs, batch))?;
+
+        if check.is_identity() {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationFailure)
+        }
+    }
+
+    /// Like [`BatchVerifier::check_batch`], but computes the terms of
+    /// chunks of the batch on up to `num_threads` threads.
+    #[cfg(feature = "std")]
+    fn check_batch_parallel<R: RngCore + CryptoRng>(
+        &self,
+        proofs: &[BatchableProof],
+        batch: &[usize],
+        num_threads: usize,
+        rng: &mut R,
+    ) -> Result<(), ProofError> {
+        let random_factors = self.random_factors(batch.len(), rng);
+        let chunk_size = parallel_chunk_size(batch.len(), num_threads);
+        let random_factors = &random_factors;
+
+        let partial_checks = thread::scope(|scope| {
+            let handles = batch
+                .chunks(chunk_size)
+                .enumerate()
+                .map(|(n, chunk)| {
+                    scope.spawn(move || {
+                        let mut static_coeffs = vec![Scalar::zero(); self.static_points.len()];
+                        let (instance_coeffs, instance_points) = self.batch_terms(
+                            proofs,
+                            chunk,
+                            random_factors,
+                            n * chunk_size,
+                            &mut static_coeffs,
+                        );
+                        let partial_check = RistrettoPoint::optional_multiscalar_mul(
+                            instance_coeffs.row_major_entries(),
+                            instance_points.iter().map(|pt| pt.decompress()),
+                        );
+                        (static_coeffs, partial_check)
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            handles.into_iter().map(join_thread).collect::<Vec<_>>()
+        });
+
+        let mut static_coeffs = vec![Scalar::zero(); self.static_points.len()];
+        let mut check = RistrettoPoint::identity();
+        for (partial_coeffs, partial_check) in partial_checks {
+            for (coeff, partial_coeff) in static_coeffs.iter_mut().zip(partial_coeffs) {
+                *coeff += partial_coeff;
             }
+            check += partial_check.ok_or_else(|| self.decompression_error(proofs, batch))?;
+        }
+        check += RistrettoPoint::optional_multiscalar_mul(
+            &static_coeffs,
+            self.static_points.iter().map(|pt| pt.decompress()),
+        )
+        .ok_or_else(|| self.decompression_error(proofs, batch))?;
+
+        if check.is_identity() {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationFailure)
         }
+    }
 
-        // Compute the challenge value for each proof
-        let minus_c = self
-            .transcripts
-            .iter_mut()
-            .map(|trans| -trans.get_challenge(b"chal"))
-            .collect::<Vec<_>>();
+    /// Draw one random factor per constraint and proof in a batch of
+    /// `batch_len` proofs, constraint by constraint.
+    fn random_factors<R: RngCore + CryptoRng>(&self, batch_len: usize, rng: &mut R) -> Matrix<Scalar> {
+        let mut random_factors = Matrix::<Scalar>::new(self.constraints.len(), batch_len);
+        for i in 0..self.constraints.len() {
+            for k in 0..batch_len {
+                random_factors[(i, k)] = Scalar::from(rng.gen::<u128>());
+            }
+        }
+        random_factors
+    }
 
-        let num_s = self.static_points.len();
+    /// Compute the terms of the combined verification check for the
+    /// proofs in `batch`, whose random factors are in the columns of
+    /// `random_factors` starting at `offset`.
+    ///
+    /// The coefficients of the static points are added to
+    /// `static_coeffs`, and the coefficients of the instance points
+    /// and commitments are returned with the points.
+    fn batch_terms(
+        &self,
+        proofs: &[BatchableProof],
+        batch: &[usize],
+        random_factors: &Matrix<Scalar>,
+        offset: usize,
+        static_coeffs: &mut [Scalar],
+    ) -> (Matrix<Scalar>, Vec<CompressedRistretto>) {
         let num_i = self.instance_points.len();
         let num_c = self.constraints.len();
+        let layout = self.layout();
 
-        let mut static_coeffs = vec![Scalar::zero(); num_s];
-        let mut instance_coeffs = Matrix::<Scalar>::new(num_i + num_c, self.batch_size);
+        let mut instance_coeffs = Matrix::<Scalar>::new(num_i + num_c, batch.len());
 
         for i in 0..num_c {
-            let (ref lhs_var, ref rhs_lc) = self.constraints[i];
-            for j in 0..self.batch_size {
-                let random_factor = Scalar::from(thread_rng().gen::<u128>());
+            let (clause_nr, ref lhs, ref rhs_lc) = self.constraints[i];
+            let responses = rhs_lc
+                .iter()
+                .map(|(sc_var, _pt_var)| layout.response_index(clause_nr, sc_var.0))
+                .collect::<Vec<_>>();
+            for (k, &j) in batch.iter().enumerate() {
+                let random_factor = random_factors[(i, offset + k)];
+                let minus_c = -proofs[j].challenges[clause_nr - 1];
 
-                // rand*( sum(P_i, resp_i) - c * Q - Q_com) == 0
+                // rand*( sum(P_i, resp_i) - c * sum(Q_j, a_j) - Q_com) == 0
 
-                instance_coeffs[(num_i + i, j)] -= random_factor;
+                instance_coeffs[(num_i + i, k)] -= random_factor;
 
-                match lhs_var {
-                    PointVar::Static(var_idx) => {
-                        static_coeffs[*var_idx] += random_factor * minus_c[j];
-                    }
-                    PointVar::Instance(var_idx) => {
-                        instance_coeffs[(*var_idx, j)] += random_factor * minus_c[j];
+                for (coeff, pt_var) in lhs {
+                    match pt_var {
+                        PointVar::Static(var_idx) => {
+                            static_coeffs[*var_idx] += random_factor * minus_c * coeff;
+                        }
+                        PointVar::Instance(var_idx) => {
+                            instance_coeffs[(*var_idx, k)] += random_factor * minus_c * coeff;
+                        }
                     }
                 }
 
-                for (sc_var, pt_var) in rhs_lc {
-                    let resp = proofs[j].responses[sc_var.0];
+                for ((_sc_var, pt_var), response) in rhs_lc.iter().zip(responses.iter()) {
+                    let resp = proofs[j].responses[*response];
                     match pt_var {
                         PointVar::Static(var_idx) => {
                             static_coeffs[*var_idx] += random_factor * resp;
                         }
                         PointVar::Instance(var_idx) => {
-                            instance_coeffs[(*var_idx, j)] += random_factor * resp;
+                            instance_coeffs[(*var_idx, k)] += random_factor * resp;
                         }
                     }
                 }
             }
         }
 
-        let mut instance_points = self.instance_points.clone();
+        let mut flat_instance_points = Vec::with_capacity((num_i + num_c) * batch.len());
+        for points in self.instance_points.iter() {
+            flat_instance_points.extend(batch.iter().map(|&j| points[j]));
+        }
         for i in 0..num_c {
-            let ith_commitments = proofs.iter().map(|proof| proof.commitments[i]);
-            instance_points.push(ith_commitments.collect());
+            flat_instance_points.extend(batch.iter().map(|&j| proofs[j].commitments[i]));
         }
 
-        let flat_instance_points = instance_points
-            .iter()
-            .flat_map(|inner| inner.iter().cloned())
-            .collect::<Vec<CompressedRistretto>>();
+        (instance_coeffs, flat_instance_points)
+    }
 
-        let check = RistrettoPoint::optional_multiscalar_mul(
-            static_coeffs
-                .iter()
-                .chain(instance_coeffs.row_major_entries()),
-            self.static_points
-                .iter()
-                .chain(flat_instance_points.iter())
-                .map(|pt| pt.decompress()),
-        )
-        .ok_or(ProofError::VerificationFailure)?;
+    /// Check that every static point is a valid encoding of a point
+    /// other than the identity.
+    ///
+    /// A bad static point fails the check of every batch, so it must
+    /// be reported as such rather than blamed on the proofs.
+    fn check_static_points(&self) -> Result<(), ProofError> {
+        for (point, label) in self.static_points.iter().zip(self.static_point_labels.iter()) {
+            match point.decompress() {
+                None => {
+                    return Err(ProofError::InvalidPoint {
+                        label: label_string(label),
+                    })
+                }
+                Some(point) if point.is_identity() => {
+                    return Err(ProofError::IdentityPoint {
+                        label: label_string(label),
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+        Ok(())
+    }
 
-        if check.is_identity() {
-            Ok(())
-        } else {
-            Err(ProofError::VerificationFailure)
+    /// Find the first point which fails to decompress, to report
+    /// which one made the verification check fail.
+    fn decompression_error(&self, proofs: &[BatchableProof], batch: &[usize]) -> ProofError {
+        if let Err(e) = self.check_static_points() {
+            return e;
+        }
+        for &j in batch {
+            for (points, label) in self.instance_points.iter().zip(self.instance_point_labels.iter()) {
+                if points[j].decompress().is_none() {
+                    return ProofError::InvalidPoint {
+                        label: label_string(label),
+                    }
+                    .in_batch(j);
+                }
+            }
+            if let Some(i) = proofs[j].commitments.iter().position(|com| com.decompress().is_none()) {
+                return ProofError::InvalidCommitment { constraint: i }.in_batch(j);
+            }
         }
+        ProofError::VerificationFailure
     }
 }
 
+/// The size of the chunks to split `len` items into to spread them
+/// over `num_threads` threads.
+#[cfg(feature = "std")]
+pub(crate) fn parallel_chunk_size(len: usize, num_threads: usize) -> usize {
+    let num_threads = num_threads.max(1);
+    ((len + num_threads - 1) / num_threads).max(1)
+}
+
+/// Wait for a worker thread, propagating its panic if it panicked.
+#[cfg(feature = "std")]
+fn join_thread<T>(handle: thread::ScopedJoinHandle<'_, T>) -> T {
+    handle.join().unwrap_or_else(|e| panic::resume_unwind(e))
+}
+
 impl<'a> SchnorrCS for BatchVerifier<'a> {
     type ScalarVar = ScalarVar;
     type PointVar = PointVar;
     type SubroutineVar = BatchVerifier<'a>;
 
-    fn constrain(&mut self, _clause_nr: usize, lhs: PointVar, linear_combination: Vec<(ScalarVar, PointVar)>) {
-        self.constraints.push((lhs, linear_combination));
+    fn constrain_combination(
+        &mut self,
+        lhs: Vec<(Scalar, PointVar)>,
+        linear_combination: Vec<(ScalarVar, PointVar)>,
+    ) {
+        check_lhs(&lhs);
+        let clause_nr = self.clauses.next_constraint();
+        self.constraints.push((clause_nr, lhs, linear_combination));
     }
 
     fn add_subroutine(&mut self, subroutine: BatchVerifier<'a>) {
         self.subroutines.push(subroutine);
     }
+
+    fn clauses(&mut self) -> &mut Clauses {
+        &mut self.clauses
+    }
 }
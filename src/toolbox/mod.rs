@@ -11,43 +11,92 @@
 //!
 //! The `SchnorrCS` trait defines the common constraint system API
 //! used for specifying proof statements; it is implemented by the
-//! `Prover`, `Verifier`, and `BatchVerifier` structs.
+//! `Prover`, `BatchProver`, `Verifier`, `BatchVerifier`,
+//! `PreparedVerifier`, and `StatementAnalyzer` structs.
 //!
 //! Roughly speaking, the tools fit together in the following way:
 //!
 //! * Statements are defined as generic functions which take a
-//! `SchnorrCS` implementation and some variables,
-//! and add the proof statements to the constraint system;
+//!   `SchnorrCS` implementation and some variables,
+//!   and add the proof statements to the constraint system;
 //!
 //! * To create a proof, construct a `Prover`,
-//! allocate and assign variables, pass the prover and the variables
-//! to the generic statement function, then consume the prover to
-//! obtain a proof.
+//!   allocate and assign variables, pass the prover and the variables
+//!   to the generic statement function, then consume the prover to
+//!   obtain a proof.
+//!
+//! * To create many proofs of one statement at once, construct a
+//!   `BatchProver` with one assignment per proof for each variable,
+//!   and use it like a `Prover`.
 //!
 //! * To verify a proof, construct a `Verifier`,
-//! allocate and assign variables, pass the verifier and the variables
-//! to the generic statement function, then consume the verifier to
-//! obtain a verification result.
+//!   allocate and assign variables, pass the verifier and the variables
+//!   to the generic statement function, then consume the verifier to
+//!   obtain a verification result.
+//!
+//! * To batch-verify proofs of different statements together, set up
+//!   a `Verifier` for each proof as above, then add it to a
+//!   `MultiBatchVerifier` together with the proof.
+//!
+//! * To verify many proofs of one statement with the same common
+//!   points, record the statement once with a `PreparedVerifier`,
+//!   then use the resulting `PreparedStatement` for each proof, or
+//!   feed the proofs to a `StreamingVerifier` one at a time.
+//!
+//! * To check a statement for common mistakes, such as secrets which
+//!   appear in no constraint, record it with a `StatementAnalyzer`.
+//!
+//! * To write code generic over a statement defined with
+//!   [`define_proof`], use the `Statement` trait, which is
+//!   implemented by the `Statement` type of each generated module.
+//!
+//! * To verify proofs of several statements received as
+//!   `ProofEnvelope`s, register the statements with a
+//!   `StatementRegistry`, which routes each envelope by its label.
+//!
+//! Without the `std` feature, only the `_with_rng` variants of the
+//! randomized functions are available, and the caller must supply
+//! the randomness.
 //!
 //! Note that the expansion of the [`define_proof`] macro contains a
 //! public `internal` module with the generated proof statement
 //! function, making it possible to combine generated and hand-crafted
 //! proof statements into the same constraint system.
 
+/// Implements checks of proof statements for common mistakes.
+pub mod analysis;
+/// Implements creation of many proofs of one statement at once.
+pub mod batch_prover;
 /// Implements batch verification of batchable proofs.
 pub mod batch_verifier;
+/// Implements batch verification of batchable proofs of different statements.
+pub mod multi_batch_verifier;
+/// Implements reusable verification of many proofs of one statement.
+pub mod prepared_verifier;
 /// Implements proof creation.
 pub mod prover;
+/// Implements verification of proofs of several statements, routed by label.
+pub mod registry;
+/// Implements batch verification of a stream of proofs in bounded memory.
+pub mod streaming_verifier;
 /// Implements proof verification of compact and batchable proofs.
 pub mod verifier;
-/// Implements shamir secret sharing as needed
+/// Implements the sharing of the challenge among the clauses of a statement.
 pub mod shamir_secrets;
 
+#[cfg(feature = "std")]
+use rand::thread_rng;
+use rand::{CryptoRng, RngCore};
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
 use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
 use curve25519_dalek::scalar::Scalar;
 use curve25519_dalek::traits::IsIdentity;
 
-use crate::{ProofError, Transcript};
+use crate::errors::label_string;
+use crate::{ProofEnvelope, ProofError, Transcript};
 
 /// An interface for specifying proof statements, common between
 /// provers and verifiers.
@@ -85,6 +134,24 @@ use crate::{ProofError, Transcript};
 /// proof, independently of the specification of the statement, by
 /// constructing a constraint system and then passing it to multiple
 /// statement functions.
+///
+/// A statement is a disjunction of clauses, each of which is a
+/// conjunction of constraints.  Constraints added directly form a
+/// single clause.  To prove one of several alternatives, add them
+/// with [`SchnorrCS::or`]: each constraint added inside it is an
+/// alternative on its own, and [`SchnorrCS::and`] groups several
+/// constraints into one alternative.  For instance, to prove that
+/// `A = G ^ x`, or that both `B = G ^ y` and `C = H ^ y`, one could
+/// write
+/// ```rust,ignore
+/// cs.or(|cs| {
+///     cs.constrain(A, vec![(x, G)]);
+///     cs.and(|cs| {
+///         cs.constrain(B, vec![(y, G)]);
+///         cs.constrain(C, vec![(y, H)]);
+///     });
+/// });
+/// ```
 pub trait SchnorrCS {
     /// A handle for a scalar variable in the constraint system.
     type ScalarVar: Copy;
@@ -96,33 +163,430 @@ pub trait SchnorrCS {
     /// Add a constraint of the form `lhs = linear_combination`.
     fn constrain(
         &mut self,
-        clause_nr: usize,
         lhs: Self::PointVar,
         linear_combination: Vec<(Self::ScalarVar, Self::PointVar)>,
+    ) {
+        self.constrain_combination(vec![(Scalar::one(), lhs)], linear_combination);
+    }
+
+    /// Add a constraint of the form `lhs = linear_combination`, where
+    /// `lhs` is a combination of public points with public
+    /// coefficients, such as `A - B`.
+    ///
+    /// The prover's commitment for the constraint is labeled with the
+    /// label of the first point of `lhs`.
+    ///
+    /// # Panics
+    ///
+    /// If `lhs` is empty.
+    fn constrain_combination(
+        &mut self,
+        lhs: Vec<(Scalar, Self::PointVar)>,
+        linear_combination: Vec<(Self::ScalarVar, Self::PointVar)>,
     );
 
     fn add_subroutine(&mut self, subroutine: Self::SubroutineVar);
+
+    /// The clauses of the statement built so far.
+    fn clauses(&mut self) -> &mut Clauses;
+
+    /// Add a disjunction to the statement, whose alternatives are
+    /// the constraints and conjunctions added by `alternatives`.
+    ///
+    /// A statement has at most one disjunction: the proof holds one
+    /// challenge per clause, so a conjunction of disjunctions can't
+    /// be expressed.
+    ///
+    /// # Panics
+    ///
+    /// If the disjunction is nested in another one, if the statement
+    /// already has a disjunction, or if the statement also has
+    /// constraints outside of a disjunction.
+    fn or<F: FnOnce(&mut Self)>(&mut self, alternatives: F)
+    where
+        Self: Sized,
+    {
+        self.clauses().begin_disjunction();
+        alternatives(self);
+        self.clauses().end_disjunction();
+    }
+
+    /// Add the constraints added by `constraints` as a conjunction.
+    ///
+    /// Inside [`SchnorrCS::or`], the conjunction is one alternative
+    /// of the disjunction; otherwise, it only groups the constraints.
+    fn and<F: FnOnce(&mut Self)>(&mut self, constraints: F)
+    where
+        Self: Sized,
+    {
+        let opened = self.clauses().begin_conjunction();
+        constraints(self);
+        if opened {
+            self.clauses().end_conjunction();
+        }
+    }
+}
+
+/// Tracks which clause of a statement each constraint belongs to,
+/// as the statement is built with [`SchnorrCS::or`] and
+/// [`SchnorrCS::and`].
+///
+/// Clauses are numbered from 1, in the order they are added.
+#[derive(Clone, Debug, Default)]
+pub struct Clauses {
+    num_clauses: usize,
+    /// Whether constraints were added outside of a disjunction.
+    has_conjunction: bool,
+    /// Whether a disjunction was added.
+    has_disjunction: bool,
+    in_disjunction: bool,
+    /// The clause of the alternative being built, if any.
+    alternative: Option<usize>,
+}
+
+impl Clauses {
+    /// The number of clauses of the statement.
+    pub fn len(&self) -> usize {
+        self.num_clauses
+    }
+
+    /// Whether the statement has no constraints.
+    pub fn is_empty(&self) -> bool {
+        self.num_clauses == 0
+    }
+
+    /// Return the clause of the next constraint of the statement.
+    pub fn next_constraint(&mut self) -> usize {
+        if self.in_disjunction {
+            match self.alternative {
+                Some(clause) => clause,
+                None => self.new_clause(),
+            }
+        } else {
+            assert!(
+                !self.has_disjunction,
+                "constraints can't be added outside of a disjunction in a statement with one"
+            );
+            self.has_conjunction = true;
+            self.num_clauses = 1;
+            1
+        }
+    }
+
+    fn new_clause(&mut self) -> usize {
+        self.num_clauses += 1;
+        self.num_clauses
+    }
+
+    pub(crate) fn begin_disjunction(&mut self) {
+        assert!(!self.in_disjunction, "disjunctions can't be nested");
+        assert!(!self.has_disjunction, "a statement can only have one disjunction");
+        assert!(
+            !self.has_conjunction,
+            "a disjunction can't be added to a statement with constraints outside of it"
+        );
+        self.in_disjunction = true;
+        self.has_disjunction = true;
+    }
+
+    pub(crate) fn end_disjunction(&mut self) {
+        self.in_disjunction = false;
+    }
+
+    /// Start a new alternative if in a disjunction, and return
+    /// whether one was started.
+    pub(crate) fn begin_conjunction(&mut self) -> bool {
+        if self.in_disjunction && self.alternative.is_none() {
+            self.alternative = Some(self.new_clause());
+            true
+        } else {
+            false
+        }
+    }
+
+    pub(crate) fn end_conjunction(&mut self) {
+        self.alternative = None;
+    }
+}
+
+/// A constraint `lhs = linear_combination`, tagged with its clause
+/// number, whose LHS is a combination of points with coefficients.
+pub(crate) type TaggedConstraint<S, P> = (usize, Vec<(Scalar, P)>, Vec<(S, P)>);
+
+/// Normalize the constraints of a statement, tagged with their clause
+/// numbers: sort the terms of each constraint by point, so that terms
+/// with the same point are adjacent in the multiscalar multiplications,
+/// and remove each constraint which is the same as an earlier one of
+/// the same clause.  The LHS of each constraint is left as it is, since
+/// its first point labels the commitment of the constraint.
+///
+/// The result only depends on the constraints, so a prover and a
+/// verifier which record the same statement normalize it identically.
+pub(crate) fn normalize_constraints<S, P>(constraints: &mut Vec<TaggedConstraint<S, P>>)
+where
+    S: Copy + Ord,
+    P: Copy + Ord,
+{
+    let mut normalized = Vec::with_capacity(constraints.len());
+    for (clause_nr, lhs, mut linear_combination) in constraints.drain(..) {
+        linear_combination.sort_unstable_by_key(|&(scalar, point)| (point, scalar));
+        let constraint = (clause_nr, lhs, linear_combination);
+        if !normalized.contains(&constraint) {
+            normalized.push(constraint);
+        }
+    }
+    *constraints = normalized;
+}
+
+/// Check that the LHS of a constraint has at least one point.
+pub(crate) fn check_lhs<T>(lhs: &[T]) {
+    assert!(!lhs.is_empty(), "the LHS of a constraint must have at least one point");
+}
+
+/// The coefficient of a point in the LHS of a constraint of a
+/// statement defined with [`define_proof`].
+#[doc(hidden)]
+pub fn __integer_coefficient(coefficient: i64) -> Scalar {
+    if coefficient < 0 {
+        -Scalar::from(coefficient.unsigned_abs())
+    } else {
+        Scalar::from(coefficient as u64)
+    }
+}
+
+/// The layout of the challenges and responses of a proof.
+///
+/// A proof has one challenge per clause, which add up to the
+/// Fiat-Shamir challenge, and one response per secret variable of
+/// each clause, ordered by clause and then by variable.  A secret
+/// variable used in several clauses has a separate response in each
+/// of them, so that the responses of the clauses the prover simulates
+/// are independent of those of the clause it proves.
+pub(crate) struct ProofLayout {
+    num_clauses: usize,
+    /// The index of the response of each secret variable of each
+    /// clause, keyed by clause and variable.
+    responses: BTreeMap<(usize, usize), usize>,
+}
+
+impl ProofLayout {
+    /// Compute the layout of a proof of a statement, given the clause
+    /// and the secret variables of each of its constraints.
+    ///
+    /// A statement without constraints has a single, empty clause.
+    pub(crate) fn new<C, S>(constraints: C) -> Self
+    where
+        C: IntoIterator<Item = (usize, S)>,
+        S: IntoIterator<Item = usize>,
+    {
+        let mut num_clauses = 1;
+        let mut responses = BTreeMap::new();
+        for (clause_nr, scalars) in constraints {
+            num_clauses = num_clauses.max(clause_nr);
+            for scalar in scalars {
+                responses.insert((clause_nr, scalar), 0);
+            }
+        }
+        for (index, response) in responses.values_mut().enumerate() {
+            *response = index;
+        }
+        ProofLayout {
+            num_clauses,
+            responses,
+        }
+    }
+
+    /// The number of challenges of a proof, one per clause.
+    pub(crate) fn num_challenges(&self) -> usize {
+        self.num_clauses
+    }
+
+    /// The number of responses of a proof.
+    pub(crate) fn num_responses(&self) -> usize {
+        self.responses.len()
+    }
+
+    /// The index of the response of the secret variable `scalar` in
+    /// the clause `clause_nr`.
+    pub(crate) fn response_index(&self, clause_nr: usize, scalar: usize) -> usize {
+        self.responses[&(clause_nr, scalar)]
+    }
+
+    /// The clause and secret variable of each response, in order.
+    pub(crate) fn response_slots(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.responses.keys().copied()
+    }
+
+    /// Check that a proof has as many challenges and responses as
+    /// the layout.
+    pub(crate) fn check_sizes(&self, challenges: &[Scalar], responses: &[Scalar]) -> Result<(), ProofError> {
+        if responses.len() != self.num_responses() {
+            return Err(ProofError::WrongResponseCount {
+                expected: self.num_responses(),
+                found: responses.len(),
+            });
+        }
+        if challenges.len() != self.num_challenges() {
+            return Err(ProofError::WrongChallengeCount {
+                expected: self.num_challenges(),
+                found: challenges.len(),
+            });
+        }
+        Ok(())
+    }
 }
 
 pub trait IsSigmaProtocol {
     type Proof: Clone;
 
-    fn commit(&mut self) -> Result<(), ProofError>;
+    #[cfg(feature = "std")]
+    fn commit(&mut self) -> Result<(), ProofError> {
+        self.commit_with_rng(&mut thread_rng())
+    }
+    fn commit_with_rng<R: RngCore + CryptoRng>(&mut self, rng: &mut R) -> Result<(), ProofError>;
     fn challenge(&mut self);
     fn response(&mut self);
 }
 
+/// A proof statement, with functions to prove and verify it.
+///
+/// Each module generated by [`define_proof`] contains a unit type
+/// `Statement` implementing this trait with the module's functions,
+/// which allows writing code generic over the statement, such as a
+/// credential system parameterised by its presentation proof.
+///
+/// The provided methods without an `rng` parameter use
+/// `thread_rng()`, and need the `std` feature.
+pub trait Statement {
+    /// The domain separation label of the statement.
+    const PROOF_LABEL: &'static str;
+    /// A human-readable description of the statement.
+    const DESCRIPTION: &'static str;
+
+    /// The assignments to the secret and public variables of a proof.
+    type ProveAssignments<'a>;
+    /// The assignments to the public variables of a proof.
+    type VerifyAssignments<'a>;
+    /// The assignments to the public variables of a batch of proofs.
+    type BatchVerifyAssignments;
+    /// The compressed public points, returned by the prover.
+    type CompressedPoints;
+    /// A proof in compact format.
+    type CompactProof;
+    /// A proof in batchable format.
+    type BatchableProof;
+    /// The assignments to the instance variables of a proof.
+    type Instance;
+    /// The assignments to the common variables.
+    type CommonPoints;
+
+    /// Produce a proof in compact format, hedging the blinding
+    /// factors with randomness from `rng`.
+    fn prove_compact_with_rng<R: RngCore + CryptoRng>(
+        transcript: &mut Transcript,
+        assignments: Self::ProveAssignments<'_>,
+        rng: &mut R,
+    ) -> Result<(Self::CompactProof, Self::CompressedPoints), ProofError>;
+
+    /// Produce a proof in batchable format, hedging the blinding
+    /// factors with randomness from `rng`.
+    fn prove_batchable_with_rng<R: RngCore + CryptoRng>(
+        transcript: &mut Transcript,
+        assignments: Self::ProveAssignments<'_>,
+        rng: &mut R,
+    ) -> Result<(Self::BatchableProof, Self::CompressedPoints), ProofError>;
+
+    /// Verify a proof in compact format.
+    fn verify_compact(
+        proof: &Self::CompactProof,
+        transcript: &mut Transcript,
+        assignments: Self::VerifyAssignments<'_>,
+    ) -> Result<(), ProofError>;
+
+    /// Verify a proof in batchable format, drawing the verifier's
+    /// random factors from `rng`.
+    fn verify_batchable_with_rng<R: RngCore + CryptoRng>(
+        proof: &Self::BatchableProof,
+        transcript: &mut Transcript,
+        assignments: Self::VerifyAssignments<'_>,
+        rng: &mut R,
+    ) -> Result<(), ProofError>;
+
+    /// Verify a batch of proofs in batchable format, drawing the
+    /// verifier's random factors from `rng`.
+    fn batch_verify_with_rng<R: RngCore + CryptoRng>(
+        proofs: &[Self::BatchableProof],
+        transcripts: Vec<&mut Transcript>,
+        assignments: Self::BatchVerifyAssignments,
+        rng: &mut R,
+    ) -> Result<(), ProofError>;
+
+    /// Verify the proof in a [`ProofEnvelope`], whose statement
+    /// instance is completed with the `common` points the verifier
+    /// expects.
+    ///
+    /// Returns [`ProofError::UnknownStatement`] if the envelope is for
+    /// a different statement.
+    fn verify_envelope(
+        envelope: &ProofEnvelope,
+        transcript: &mut Transcript,
+        common: &Self::CommonPoints,
+    ) -> Result<(), ProofError>;
+
+    /// Produce a proof in compact format.
+    #[cfg(feature = "std")]
+    fn prove_compact(
+        transcript: &mut Transcript,
+        assignments: Self::ProveAssignments<'_>,
+    ) -> Result<(Self::CompactProof, Self::CompressedPoints), ProofError> {
+        Self::prove_compact_with_rng(transcript, assignments, &mut thread_rng())
+    }
+
+    /// Produce a proof in batchable format.
+    #[cfg(feature = "std")]
+    fn prove_batchable(
+        transcript: &mut Transcript,
+        assignments: Self::ProveAssignments<'_>,
+    ) -> Result<(Self::BatchableProof, Self::CompressedPoints), ProofError> {
+        Self::prove_batchable_with_rng(transcript, assignments, &mut thread_rng())
+    }
+
+    /// Verify a proof in batchable format.
+    #[cfg(feature = "std")]
+    fn verify_batchable(
+        proof: &Self::BatchableProof,
+        transcript: &mut Transcript,
+        assignments: Self::VerifyAssignments<'_>,
+    ) -> Result<(), ProofError> {
+        Self::verify_batchable_with_rng(proof, transcript, assignments, &mut thread_rng())
+    }
+
+    /// Verify a batch of proofs in batchable format.
+    #[cfg(feature = "std")]
+    fn batch_verify(
+        proofs: &[Self::BatchableProof],
+        transcripts: Vec<&mut Transcript>,
+        assignments: Self::BatchVerifyAssignments,
+    ) -> Result<(), ProofError> {
+        Self::batch_verify_with_rng(proofs, transcripts, assignments, &mut thread_rng())
+    }
+}
+
 /// This trait defines the wire format for how the constraint system
 /// interacts with the proof transcript.
 pub trait TranscriptProtocol {
     /// Appends `label` to the transcript as a domain separator.
-    fn domain_sep(&mut self, label: &'static [u8]);
+    fn domain_sep(&mut self, label: &[u8]);
+
+    /// Append the `message` of a signature of knowledge to the
+    /// transcript, domain-separated from the statement and the proof.
+    fn append_signed_message(&mut self, message: &[u8]);
 
     /// Append the `label` for a scalar variable to the transcript.
     ///
     /// Note: this does not commit its assignment, which is secret,
     /// and only serves to bind the proof to the variable allocations.
-    fn append_scalar_var(&mut self, label: &'static [u8]);
+    fn append_scalar_var(&mut self, label: &[u8]);
 
     /// Append a point variable to the transcript, for use by a prover.
     ///
@@ -131,20 +595,27 @@ pub trait TranscriptProtocol {
     /// discarded if it's unused.
     fn append_point_var(
         &mut self,
-        label: &'static [u8],
+        label: &[u8],
         point: &RistrettoPoint,
     ) -> CompressedRistretto;
 
+    /// Append an already-compressed point variable to the transcript,
+    /// for use by a prover.
+    ///
+    /// This appends the same data as [`TranscriptProtocol::append_point_var`].
+    fn append_compressed_point_var(&mut self, label: &[u8], point: &CompressedRistretto);
+
     /// Check that point variable is not the identity and
     /// append it to the transcript, for use by a verifier.
     ///
     /// Returns `Ok(())` if the point is not the identity point (and
-    /// therefore generates the full ristretto255 group).
+    /// therefore generates the full ristretto255 group), and
+    /// [`ProofError::IdentityPoint`] otherwise.
     ///
     /// Using this function prevents small-subgroup attacks.
     fn validate_and_append_point_var(
         &mut self,
-        label: &'static [u8],
+        label: &[u8],
         point: &CompressedRistretto,
     ) -> Result<(), ProofError>;
 
@@ -156,7 +627,7 @@ pub trait TranscriptProtocol {
     /// discarded if it's unused.
     fn append_blinding_commitment(
         &mut self,
-        label: &'static [u8],
+        label: &[u8],
         point: &RistrettoPoint,
     ) -> CompressedRistretto;
 
@@ -164,12 +635,13 @@ pub trait TranscriptProtocol {
     /// commit it to the transcript, for use by a verifier.
     ///
     /// Returns `Ok(())` if the point is not the identity point (and
-    /// therefore generates the full ristretto255 group).
+    /// therefore generates the full ristretto255 group), and
+    /// [`ProofError::IdentityPoint`] with the `label` otherwise.
     ///
     /// Using this function prevents small-subgroup attacks.
     fn validate_and_append_blinding_commitment(
         &mut self,
-        label: &'static [u8],
+        label: &[u8],
         point: &CompressedRistretto,
     ) -> Result<(), ProofError>;
 
@@ -178,33 +650,44 @@ pub trait TranscriptProtocol {
 }
 
 impl TranscriptProtocol for Transcript {
-    fn domain_sep(&mut self, label: &'static [u8]) {
+    fn domain_sep(&mut self, label: &[u8]) {
         self.append_message(b"dom-sep", b"schnorrzkp/1.0/ristretto255");
         self.append_message(b"dom-sep", label);
     }
 
-    fn append_scalar_var(&mut self, label: &'static [u8]) {
+    fn append_signed_message(&mut self, message: &[u8]) {
+        self.append_message(b"dom-sep", b"schnorrzkp/1.0/signature");
+        self.append_message(b"sig-msg", message);
+    }
+
+    fn append_scalar_var(&mut self, label: &[u8]) {
         self.append_message(b"scvar", label);
     }
 
     fn append_point_var(
         &mut self,
-        label: &'static [u8],
+        label: &[u8],
         point: &RistrettoPoint,
     ) -> CompressedRistretto {
         let encoding = point.compress();
-        self.append_message(b"ptvar", label);
-        self.append_message(b"val", encoding.as_bytes());
+        self.append_compressed_point_var(label, &encoding);
         encoding
     }
 
+    fn append_compressed_point_var(&mut self, label: &[u8], point: &CompressedRistretto) {
+        self.append_message(b"ptvar", label);
+        self.append_message(b"val", point.as_bytes());
+    }
+
     fn validate_and_append_point_var(
         &mut self,
-        label: &'static [u8],
+        label: &[u8],
         point: &CompressedRistretto,
     ) -> Result<(), ProofError> {
         if point.is_identity() {
-            return Err(ProofError::VerificationFailure);
+            return Err(ProofError::IdentityPoint {
+                label: label_string(label),
+            });
         }
         self.append_message(b"ptvar", label);
         self.append_message(b"val", point.as_bytes());
@@ -213,7 +696,7 @@ impl TranscriptProtocol for Transcript {
 
     fn append_blinding_commitment(
         &mut self,
-        label: &'static [u8],
+        label: &[u8],
         point: &RistrettoPoint,
     ) -> CompressedRistretto {
         let encoding = point.compress();
@@ -224,11 +707,13 @@ impl TranscriptProtocol for Transcript {
 
     fn validate_and_append_blinding_commitment(
         &mut self,
-        label: &'static [u8],
+        label: &[u8],
         point: &CompressedRistretto,
     ) -> Result<(), ProofError> {
         if point.is_identity() {
-            return Err(ProofError::VerificationFailure);
+            return Err(ProofError::IdentityPoint {
+                label: label_string(label),
+            });
         }
         self.append_message(b"blindcom", label);
         self.append_message(b"val", point.as_bytes());
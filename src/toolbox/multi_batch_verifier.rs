@@ -0,0 +1,128 @@
+#[cfg(feature = "std")]
+use rand::thread_rng;
+use rand::{CryptoRng, Rng, RngCore};
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::{IsIdentity, VartimeMultiscalarMul};
+
+use crate::toolbox::verifier::Verifier;
+use crate::{BatchableProof, ProofError};
+
+/// A verification equation, given as a list of terms whose sum must
+/// be the identity.
+pub(crate) type Equation = Vec<(Scalar, CompressedRistretto)>;
+
+/// Used to batch-verify proofs of different statements together.
+///
+/// Unlike the [`BatchVerifier`](crate::toolbox::batch_verifier::BatchVerifier),
+/// which verifies many proofs of a single statement, each proof added
+/// to a [`MultiBatchVerifier`] is described by its own [`Verifier`],
+/// with its own transcript, proof label and variable assignments.
+///
+/// To use a [`MultiBatchVerifier`], construct one using
+/// [`MultiBatchVerifier::new()`].  Then, for each proof, construct
+/// and set up a [`Verifier`] as for [`Verifier::verify_batchable`],
+/// but pass it to [`MultiBatchVerifier::add_proof`] together with the
+/// proof instead.  Finally, use [`MultiBatchVerifier::verify`] to
+/// check all of the proofs with a single multiscalar multiplication.
+///
+/// Points with the same encoding are merged in the combined check,
+/// so points common to several statements, such as a basepoint, only
+/// contribute one term, and are only decompressed once.
+#[derive(Default)]
+pub struct MultiBatchVerifier {
+    points: Vec<RistrettoPoint>,
+    point_indices: BTreeMap<[u8; 32], usize>,
+    equations: Vec<Vec<(Scalar, usize)>>,
+    num_proofs: usize,
+}
+
+impl MultiBatchVerifier {
+    /// Construct a new, empty, multi-statement batch verifier.
+    pub fn new() -> Self {
+        MultiBatchVerifier::default()
+    }
+
+    /// The number of proofs added to the batch.
+    pub fn len(&self) -> usize {
+        self.num_proofs
+    }
+
+    /// Whether no proofs have been added to the batch.
+    pub fn is_empty(&self) -> bool {
+        self.num_proofs == 0
+    }
+
+    /// Consume the `verifier` for a proof statement to add the
+    /// verification equations for its `proof` to the batch.
+    ///
+    /// Returns an error without adding the proof if the proof is
+    /// malformed, one of its points or commitments is not a valid
+    /// point encoding, or its challenge doesn't match the transcript.
+    pub fn add_proof(&mut self, mut verifier: Verifier, proof: &BatchableProof) -> Result<(), ProofError> {
+        let equations = verifier.batch_equations(proof)?;
+
+        let num_points = self.points.len();
+        let num_equations = self.equations.len();
+        for equation in equations {
+            let terms = equation
+                .into_iter()
+                .map(|(coeff, point)| Some((coeff, self.point_index(point)?)))
+                .collect::<Option<Vec<_>>>();
+            match terms {
+                Some(terms) => self.equations.push(terms),
+                None => {
+                    // Forget the points and equations of this proof
+                    self.points.truncate(num_points);
+                    self.point_indices.retain(|_, index| *index < num_points);
+                    self.equations.truncate(num_equations);
+                    return Err(verifier.decompression_error(&proof.commitments));
+                }
+            }
+        }
+        self.num_proofs += 1;
+        Ok(())
+    }
+
+    /// The index of a point in the batch, decompressing and adding it
+    /// if it's new, or `None` if it fails to decompress.
+    fn point_index(&mut self, point: CompressedRistretto) -> Option<usize> {
+        if let Some(index) = self.point_indices.get(point.as_bytes()) {
+            return Some(*index);
+        }
+        self.points.push(point.decompress()?);
+        self.point_indices.insert(point.to_bytes(), self.points.len() - 1);
+        Some(self.points.len() - 1)
+    }
+
+    /// Consume the verifier to check all the proofs in the batch.
+    #[cfg(feature = "std")]
+    pub fn verify(self) -> Result<(), ProofError> {
+        self.verify_with_rng(&mut thread_rng())
+    }
+
+    /// Consume the verifier to check all the proofs in the batch,
+    /// drawing the random factors used to combine the verification
+    /// equations from `rng`.
+    pub fn verify_with_rng<R: RngCore + CryptoRng>(self, rng: &mut R) -> Result<(), ProofError> {
+        let mut coeffs = vec![Scalar::zero(); self.points.len()];
+        for equation in self.equations.iter() {
+            let random_factor = Scalar::from(rng.gen::<u128>());
+            for (coeff, index) in equation {
+                coeffs[*index] += random_factor * coeff;
+            }
+        }
+
+        let check = RistrettoPoint::vartime_multiscalar_mul(&coeffs, &self.points);
+
+        if check.is_identity() {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationFailure)
+        }
+    }
+}
@@ -0,0 +1,414 @@
+#[cfg(feature = "std")]
+use rand::thread_rng;
+use rand::{CryptoRng, Rng, RngCore};
+
+use alloc::vec::Vec;
+
+use curve25519_dalek::ristretto::{CompressedRistretto, VartimeRistrettoPrecomputation};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::{IsIdentity, VartimePrecomputedMultiscalarMul};
+
+use crate::errors::label_string;
+use crate::toolbox::shamir_secrets::SecretShare;
+use crate::toolbox::{check_lhs, normalize_constraints, Clauses, ProofLayout, SchnorrCS, TranscriptProtocol};
+use crate::{BatchableProof, ProofError, Transcript};
+
+/// Used to record a proof statement once, for verifying many proofs
+/// which share the assignments to the static points.
+///
+/// To use a [`PreparedVerifier`], first construct one using
+/// [`PreparedVerifier::new()`], supplying a domain separation label.
+/// Unlike the [`Verifier`](crate::toolbox::verifier::Verifier), no
+/// transcript is supplied, since the statement is replayed into a
+/// fresh transcript for each proof.
+///
+/// Allocate secret variables using [`PreparedVerifier::allocate_scalar`].
+/// Allocate points whose assignment is common to all proofs using
+/// [`PreparedVerifier::allocate_static_point`], and points which have
+/// a different assignment for each proof using
+/// [`PreparedVerifier::allocate_instance_point`].  Then use those
+/// variables to define the proof statements.
+///
+/// Finally, use [`PreparedVerifier::prepare`] to consume the verifier
+/// and produce a [`PreparedStatement`], which precomputes tables for
+/// the static points and can be reused to verify any number of proofs.
+pub struct PreparedVerifier {
+    statement: Statement,
+    clauses: Clauses,
+}
+
+/// A proof statement prepared for verification, produced by
+/// [`PreparedVerifier::prepare`].
+///
+/// The static points are decompressed and validated once, and
+/// multiplications by them use precomputed tables.  Proofs are
+/// verified by supplying the assignments to the instance points in
+/// the order they were allocated.
+pub struct PreparedStatement {
+    statement: Statement,
+    layout: ProofLayout,
+    precomputation: VartimeRistrettoPrecomputation,
+}
+
+/// The recorded allocations and constraints of a statement.
+struct Statement {
+    proof_label: Vec<u8>,
+    allocations: Vec<Allocation>,
+    num_scalars: usize,
+    static_points: Vec<CompressedRistretto>,
+    static_point_labels: Vec<Vec<u8>>,
+    instance_point_labels: Vec<Vec<u8>>,
+    constraints: Vec<Constraint>,
+}
+
+/// A variable allocation, replayed into the transcript of each proof.
+enum Allocation {
+    Scalar(Vec<u8>),
+    Static(usize),
+    Instance(usize),
+}
+
+/// A scalar variable used in prepared verification.
+#[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct ScalarVar(usize);
+
+/// A point variable used in prepared verification.
+#[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum PointVar {
+    /// A variable whose assignment is common to all proofs.
+    Static(usize),
+    /// A variable whose assignment is unique for each proof instance.
+    Instance(usize),
+}
+
+/// A constraint `lhs = linear_combination`, tagged with its clause number.
+type Constraint = (usize, Vec<(Scalar, PointVar)>, Vec<(ScalarVar, PointVar)>);
+
+impl PreparedVerifier {
+    /// Construct a new prepared verifier for the statement with the
+    /// given `proof_label`.
+    ///
+    /// The prepared verifier keeps its own copies of the labels, so
+    /// they don't need to outlive it.
+    pub fn new(proof_label: &[u8]) -> Self {
+        PreparedVerifier {
+            statement: Statement {
+                proof_label: proof_label.to_vec(),
+                allocations: Vec::default(),
+                num_scalars: 0,
+                static_points: Vec::default(),
+                static_point_labels: Vec::default(),
+                instance_point_labels: Vec::default(),
+                constraints: Vec::default(),
+            },
+            clauses: Clauses::default(),
+        }
+    }
+
+    /// Allocate a placeholder scalar variable with the given `label`.
+    pub fn allocate_scalar(&mut self, label: &[u8]) -> ScalarVar {
+        let statement = &mut self.statement;
+        statement.allocations.push(Allocation::Scalar(label.to_vec()));
+        statement.num_scalars += 1;
+        ScalarVar(statement.num_scalars - 1)
+    }
+
+    /// Allocate a point variable whose assignment is common to all proofs.
+    pub fn allocate_static_point(
+        &mut self,
+        label: &[u8],
+        assignment: CompressedRistretto,
+    ) -> Result<PointVar, ProofError> {
+        if assignment.is_identity() {
+            return Err(ProofError::IdentityPoint {
+                label: label_string(label),
+            });
+        }
+        let statement = &mut self.statement;
+        statement.static_points.push(assignment);
+        statement.static_point_labels.push(label.to_vec());
+        let index = statement.static_points.len() - 1;
+        statement.allocations.push(Allocation::Static(index));
+
+        Ok(PointVar::Static(index))
+    }
+
+    /// Allocate a point variable whose assignment is supplied with
+    /// each proof.
+    pub fn allocate_instance_point(&mut self, label: &[u8]) -> PointVar {
+        let statement = &mut self.statement;
+        statement.instance_point_labels.push(label.to_vec());
+        let index = statement.instance_point_labels.len() - 1;
+        statement.allocations.push(Allocation::Instance(index));
+
+        PointVar::Instance(index)
+    }
+
+    /// Normalize the statement, removing duplicate constraints and
+    /// ordering the terms of each constraint, as the prover did with
+    /// [`Prover::normalize`](crate::toolbox::prover::Prover::normalize).
+    pub fn normalize(&mut self) {
+        normalize_constraints(&mut self.statement.constraints);
+    }
+
+    /// Consume the verifier to decompress the static points and
+    /// precompute tables for them.
+    pub fn prepare(self) -> Result<PreparedStatement, ProofError> {
+        let statement = self.statement;
+        let static_points = statement
+            .static_points
+            .iter()
+            .zip(statement.static_point_labels.iter())
+            .map(|(pt, label)| {
+                pt.decompress().ok_or_else(|| ProofError::InvalidPoint {
+                    label: label_string(label),
+                })
+            })
+            .collect::<Result<Vec<_>, ProofError>>()?;
+
+        let layout = ProofLayout::new(statement.constraints.iter().map(|(clause_nr, _lhs_var, rhs_lc)| {
+            (*clause_nr, rhs_lc.iter().map(|(sc_var, _pt_var)| sc_var.0))
+        }));
+
+        Ok(PreparedStatement {
+            precomputation: VartimeRistrettoPrecomputation::new(static_points),
+            statement,
+            layout,
+        })
+    }
+}
+
+impl PreparedStatement {
+    /// The number of instance points to supply with each proof.
+    pub fn num_instance_points(&self) -> usize {
+        self.statement.instance_point_labels.len()
+    }
+
+    /// Verify a [`BatchableProof`], given a transcript and the
+    /// assignments to the instance points.
+    #[cfg(feature = "std")]
+    pub fn verify_batchable(
+        &self,
+        proof: &BatchableProof,
+        transcript: &mut Transcript,
+        instance_points: &[CompressedRistretto],
+    ) -> Result<(), ProofError> {
+        self.verify_batchable_with_rng(proof, transcript, instance_points, &mut thread_rng())
+    }
+
+    /// Verify a [`BatchableProof`], drawing the random factors used to
+    /// combine the verification equations from `rng`.
+    pub fn verify_batchable_with_rng<R: RngCore + CryptoRng>(
+        &self,
+        proof: &BatchableProof,
+        transcript: &mut Transcript,
+        instance_points: &[CompressedRistretto],
+        rng: &mut R,
+    ) -> Result<(), ProofError> {
+        self.append_proof(transcript, instance_points, proof)?;
+        self.check(&[(proof, instance_points)], rng)
+            .map_err(|e| match e {
+                ProofError::BatchProofFailure { source, .. } => *source,
+                e => e,
+            })
+    }
+
+    /// Verify a batch of [`BatchableProof`]s, given one transcript and
+    /// one list of instance point assignments per proof.
+    #[cfg(feature = "std")]
+    pub fn batch_verify(
+        &self,
+        proofs: &[BatchableProof],
+        transcripts: Vec<&mut Transcript>,
+        instance_points: &[Vec<CompressedRistretto>],
+    ) -> Result<(), ProofError> {
+        self.batch_verify_with_rng(proofs, transcripts, instance_points, &mut thread_rng())
+    }
+
+    /// Verify a batch of [`BatchableProof`]s, drawing the random
+    /// factors used to combine the verification equations from `rng`.
+    pub fn batch_verify_with_rng<R: RngCore + CryptoRng>(
+        &self,
+        proofs: &[BatchableProof],
+        transcripts: Vec<&mut Transcript>,
+        instance_points: &[Vec<CompressedRistretto>],
+        rng: &mut R,
+    ) -> Result<(), ProofError> {
+        if transcripts.len() != proofs.len() || instance_points.len() != proofs.len() {
+            return Err(ProofError::BatchSizeMismatch);
+        }
+
+        let mut batch = Vec::with_capacity(proofs.len());
+        for (j, ((proof, transcript), points)) in proofs
+            .iter()
+            .zip(transcripts)
+            .zip(instance_points.iter())
+            .enumerate()
+        {
+            self.append_proof(transcript, points, proof)
+                .map_err(|e| e.in_batch(j))?;
+            batch.push((proof, &points[..]));
+        }
+
+        self.check(&batch, rng)
+    }
+
+    /// Replay the statement into the `transcript`, check the sizes of
+    /// the proof, feed its commitments into the transcript, and check
+    /// the claimed challenge.
+    pub(crate) fn append_proof(
+        &self,
+        transcript: &mut Transcript,
+        instance_points: &[CompressedRistretto],
+        proof: &BatchableProof,
+    ) -> Result<(), ProofError> {
+        let statement = &self.statement;
+        if instance_points.len() != statement.instance_point_labels.len() {
+            return Err(ProofError::WrongInstanceSize {
+                expected: statement.instance_point_labels.len(),
+                found: instance_points.len(),
+            });
+        }
+
+        transcript.domain_sep(&statement.proof_label);
+        for allocation in statement.allocations.iter() {
+            match allocation {
+                Allocation::Scalar(label) => transcript.append_scalar_var(label),
+                Allocation::Static(i) => transcript.validate_and_append_point_var(
+                    &statement.static_point_labels[*i],
+                    &statement.static_points[*i],
+                )?,
+                Allocation::Instance(i) => transcript.validate_and_append_point_var(
+                    &statement.instance_point_labels[*i],
+                    &instance_points[*i],
+                )?,
+            }
+        }
+
+        self.layout.check_sizes(&proof.challenges, &proof.responses)?;
+        if proof.commitments.len() != statement.constraints.len() {
+            return Err(ProofError::WrongCommitmentCount {
+                expected: statement.constraints.len(),
+                found: proof.commitments.len(),
+            });
+        }
+
+        for (i, (commitment, (_clause_nr, lhs, _rhs_lc))) in
+            proof.commitments.iter().zip(statement.constraints.iter()).enumerate()
+        {
+            let label = match lhs[0].1 {
+                PointVar::Static(var_idx) => &statement.static_point_labels[var_idx],
+                PointVar::Instance(var_idx) => &statement.instance_point_labels[var_idx],
+            };
+            transcript
+                .validate_and_append_blinding_commitment(label, commitment)
+                .map_err(|_| ProofError::InvalidCommitment { constraint: i })?;
+        }
+
+        let challenge = transcript.get_challenge(b"chal");
+        if challenge != SecretShare::reconstruct(&proof.challenges) {
+            return Err(ProofError::ChallengeMismatch);
+        }
+        Ok(())
+    }
+
+    /// Perform the combined verification check for a batch of proofs
+    /// with their instance points.
+    pub(crate) fn check<R: RngCore + CryptoRng>(
+        &self,
+        batch: &[(&BatchableProof, &[CompressedRistretto])],
+        rng: &mut R,
+    ) -> Result<(), ProofError> {
+        let statement = &self.statement;
+        let num_i = statement.instance_point_labels.len();
+        let num_c = statement.constraints.len();
+
+        let mut static_coeffs = vec![Scalar::zero(); statement.static_points.len()];
+        let mut dynamic_coeffs = vec![Scalar::zero(); (num_i + num_c) * batch.len()];
+
+        for (j, (proof, _)) in batch.iter().enumerate() {
+            let offset = (num_i + num_c) * j;
+            // For each constraint of the form sum(Q_j, a_j) = sum(P_i, x_i),
+            // add the check rand*( sum(P_i, resp_i) - c * sum(Q_j, a_j) - Q_com ) == 0
+            for (i, (clause_nr, lhs, rhs_lc)) in statement.constraints.iter().enumerate() {
+                let random_factor = Scalar::from(rng.gen::<u128>());
+                let minus_c = -proof.challenges[clause_nr - 1];
+
+                dynamic_coeffs[offset + num_i + i] -= random_factor;
+                let terms = rhs_lc
+                    .iter()
+                    .map(|(sc_var, pt_var)| {
+                        (proof.responses[self.layout.response_index(*clause_nr, sc_var.0)], pt_var)
+                    })
+                    .chain(lhs.iter().map(|(coeff, pt_var)| (minus_c * coeff, pt_var)));
+                for (scalar, pt_var) in terms {
+                    match pt_var {
+                        PointVar::Static(var_idx) => {
+                            static_coeffs[*var_idx] += random_factor * scalar;
+                        }
+                        PointVar::Instance(var_idx) => {
+                            dynamic_coeffs[offset + var_idx] += random_factor * scalar;
+                        }
+                    }
+                }
+            }
+        }
+
+        let dynamic_points = batch.iter().flat_map(|(proof, points)| {
+            points.iter().chain(proof.commitments.iter()).map(|pt| pt.decompress())
+        });
+
+        let check = self
+            .precomputation
+            .optional_mixed_multiscalar_mul(&static_coeffs, &dynamic_coeffs, dynamic_points)
+            .ok_or_else(|| self.decompression_error(batch))?;
+
+        if check.is_identity() {
+            Ok(())
+        } else {
+            Err(ProofError::VerificationFailure)
+        }
+    }
+
+    /// Find the first point which fails to decompress, to report
+    /// which one made the verification check fail.
+    fn decompression_error(&self, batch: &[(&BatchableProof, &[CompressedRistretto])]) -> ProofError {
+        for (j, (proof, points)) in batch.iter().enumerate() {
+            let labels = self.statement.instance_point_labels.iter();
+            for (point, label) in points.iter().zip(labels) {
+                if point.decompress().is_none() {
+                    return ProofError::InvalidPoint {
+                        label: label_string(label),
+                    }
+                    .in_batch(j);
+                }
+            }
+            if let Some(i) = proof.commitments.iter().position(|com| com.decompress().is_none()) {
+                return ProofError::InvalidCommitment { constraint: i }.in_batch(j);
+            }
+        }
+        ProofError::VerificationFailure
+    }
+}
+
+impl SchnorrCS for PreparedVerifier {
+    type ScalarVar = ScalarVar;
+    type PointVar = PointVar;
+    type SubroutineVar = PreparedVerifier;
+
+    fn constrain_combination(
+        &mut self,
+        lhs: Vec<(Scalar, PointVar)>,
+        linear_combination: Vec<(ScalarVar, PointVar)>,
+    ) {
+        check_lhs(&lhs);
+        let clause_nr = self.clauses.next_constraint();
+        self.statement.constraints.push((clause_nr, lhs, linear_combination));
+    }
+
+    fn add_subroutine(&mut self, _subroutine: PreparedVerifier) {}
+
+    fn clauses(&mut self) -> &mut Clauses {
+        &mut self.clauses
+    }
+}
@@ -1,13 +1,20 @@
+#[cfg(feature = "std")]
 use rand::thread_rng;
+use rand::{CryptoRng, RngCore};
 
-use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+use core::mem;
+
+use zeroize::Zeroize;
+
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoBasepointTable, RistrettoPoint};
 use curve25519_dalek::scalar::Scalar;
 use curve25519_dalek::traits::MultiscalarMul;
 
-use crate::toolbox::{SchnorrCS, TranscriptProtocol, IsSigmaProtocol};
+use crate::toolbox::{check_lhs, normalize_constraints, Clauses, IsSigmaProtocol, ProofLayout, SchnorrCS, TranscriptProtocol};
 use crate::{BatchableProof, CompactProof, Transcript, ProofError};
 use crate::toolbox::shamir_secrets::SecretShare;
-use std::iter;
 
 /// Used to create proofs.
 ///
@@ -17,17 +24,35 @@ use std::iter;
 ///
 /// Then, allocate and assign secret ([`Prover::allocate_scalar`]) and
 /// public ([`Prover::allocate_point`]) variables, and use those
-/// variables to define the proof statements.
+/// variables to define the proof statements.  Public variables whose
+/// point is a fixed generator can instead be allocated from a
+/// precomputed table using [`Prover::allocate_point_with_table`],
+/// which speeds up the prover's commitments.
 ///
 /// Finally, use [`Prover::prove_compact`] or
 /// [`Prover::prove_batchable`] to consume the prover and produce a
-/// proof.
+/// proof.  The `_with_rng` variants of these functions take the
+/// randomness used to hedge the blinding factors from the caller
+/// instead of from `thread_rng()`.
+///
+/// To precompute the commitments of a proof ahead of time, use
+/// [`Prover::precommit`] on a prover for the same statement, and pass
+/// the resulting [`Precommitment`] to
+/// [`Prover::prove_compact_with_precommitment`] or
+/// [`Prover::prove_batchable_with_precommitment`] once the context of
+/// the proof is known.
+///
+/// The secret assignments, blinding factors, and simulated responses
+/// held by the prover are zeroized when it is dropped.
 pub struct Prover<'a> {
     transcript: &'a mut Transcript,
-    scalars: Vec<Option<Scalar>>,
-    points: Vec<RistrettoPoint>,
-    point_labels: Vec<&'static [u8]>,
-    constraints: Vec<(usize, PointVar, Vec<(ScalarVar, PointVar)>)>,
+    scalars: Cow<'a, [Option<Scalar>]>,
+    points: Cow<'a, [RistrettoPoint]>,
+    point_tables: Cow<'a, [Option<&'a RistrettoBasepointTable>]>,
+    point_labels: Cow<'a, [&'a [u8]]>,
+    constraints: Cow<'a, [Constraint]>,
+    clauses: Clauses,
+    real_clause: Option<usize>,
     subroutines: Vec<Prover<'a>>,
 
     proof: BatchableProof,
@@ -41,24 +66,62 @@ pub struct Prover<'a> {
 }
 
 /// A secret variable used during proving.
-#[derive(Copy, Clone)]
-pub struct ScalarVar(usize);
+#[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct ScalarVar(pub(crate) usize);
 
 /// A public variable used during proving.
-#[derive(Copy, Clone)]
-pub struct PointVar(usize);
+#[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct PointVar(pub(crate) usize);
+
+/// A constraint `lhs = linear_combination`, tagged with its clause number.
+pub(crate) type Constraint = (usize, Vec<(Scalar, PointVar)>, Vec<(ScalarVar, PointVar)>);
 
 impl<'a> Prover<'a> {
     /// Construct a new prover.  The `proof_label` disambiguates proof
     /// statements.
-    pub fn new(proof_label: &'static [u8], transcript: &'a mut Transcript) -> Self {
+    pub fn new(proof_label: &[u8], transcript: &'a mut Transcript) -> Self {
         transcript.domain_sep(proof_label);
         Prover {
             transcript,
-            scalars: Vec::default(),
-            points: Vec::default(),
-            point_labels: Vec::default(),
-            constraints: Vec::default(),
+            scalars: Cow::default(),
+            points: Cow::default(),
+            point_tables: Cow::default(),
+            point_labels: Cow::default(),
+            constraints: Cow::default(),
+            clauses: Clauses::default(),
+            real_clause: None,
+            subroutines: Vec::default(),
+            proof: BatchableProof::default(),
+            commitments: Vec::default(),
+            blindings: Vec::default(),
+            fake_responses: Vec::default(),
+            known_chal_shares: Vec::default(),
+            challenge: Default::default()
+        }
+    }
+
+    /// Construct a prover from variables and constraints which were
+    /// already allocated and appended to the `transcript`.
+    ///
+    /// The assignments are borrowed, so the caller is responsible for
+    /// zeroizing the secrets.
+    pub(crate) fn from_parts(
+        transcript: &'a mut Transcript,
+        scalars: &'a [Option<Scalar>],
+        points: &'a [RistrettoPoint],
+        point_tables: &'a [Option<&'a RistrettoBasepointTable>],
+        point_labels: &'a [&'a [u8]],
+        constraints: &'a [Constraint],
+    ) -> Self {
+        Prover {
+            transcript,
+            scalars: Cow::Borrowed(scalars),
+            points: Cow::Borrowed(points),
+            point_tables: Cow::Borrowed(point_tables),
+            point_labels: Cow::Borrowed(point_labels),
+            constraints: Cow::Borrowed(constraints),
+            clauses: Clauses::default(),
+            real_clause: None,
             subroutines: Vec::default(),
             proof: BatchableProof::default(),
             commitments: Vec::default(),
@@ -70,9 +133,9 @@ impl<'a> Prover<'a> {
     }
 
     /// Allocate and assign a secret variable with the given `label`.
-    pub fn allocate_scalar(&mut self, label: &'static [u8], assignment: Option<Scalar>) -> ScalarVar {
+    pub fn allocate_scalar(&mut self, label: &[u8], assignment: Option<Scalar>) -> ScalarVar {
         self.transcript.append_scalar_var(label);
-        self.scalars.push(assignment);
+        self.scalars.to_mut().push(assignment);
         ScalarVar(self.scalars.len() - 1)
     }
 
@@ -83,35 +146,321 @@ impl<'a> Prover<'a> {
     /// of that computation; it can be safely discarded.
     pub fn allocate_point(
         &mut self,
-        label: &'static [u8],
+        label: &'a [u8],
         assignment: RistrettoPoint,
     ) -> (PointVar, CompressedRistretto) {
         let compressed = self.transcript.append_point_var(label, &assignment);
-        self.points.push(assignment);
-        self.point_labels.push(label);
+        self.points.to_mut().push(assignment);
+        self.point_tables.to_mut().push(None);
+        self.point_labels.to_mut().push(label);
         (PointVar(self.points.len() - 1), compressed)
     }
 
-    /// The compact and batchable proofs differ only by which data they store.
-    fn prove_impl(mut self) -> Result<BatchableProof, ProofError> {
-        let result = self.commit();
-        if result.is_err() {
-            return Err(result.err().unwrap());
+    /// Set the clause to prove, counting from 1; the other clauses
+    /// are simulated.
+    ///
+    /// By default, the first clause whose secrets are all assigned is
+    /// proven.  If the secrets of several clauses are assigned, for
+    /// instance because clauses share secrets, this chooses which of
+    /// them to prove.
+    pub fn set_real_clause(&mut self, clause_nr: usize) {
+        self.real_clause = Some(clause_nr);
+    }
+
+    /// Allocate and assign a public variable with the given `label`,
+    /// whose assignment is the basepoint of a precomputed `table`.
+    ///
+    /// The transcript is the same as if the basepoint had been
+    /// allocated with [`Prover::allocate_point`], but multiplications
+    /// by it use the table.
+    pub fn allocate_point_with_table(
+        &mut self,
+        label: &'a [u8],
+        table: &'a RistrettoBasepointTable,
+    ) -> (PointVar, CompressedRistretto) {
+        let (var, compressed) = self.allocate_point(label, table.basepoint());
+        self.point_tables.to_mut()[var.0] = Some(table);
+        (var, compressed)
+    }
+
+    /// Normalize the statement, removing duplicate constraints and
+    /// ordering the terms of each constraint.
+    ///
+    /// This is optional, but it changes the proof, so the verifier
+    /// must normalize the statement too.
+    pub fn normalize(&mut self) {
+        normalize_constraints(self.constraints.to_mut());
+    }
+
+    /// Compute the sum of the given terms, using the precomputed
+    /// table for each point which has one.
+    fn combine(&self, terms: &[(Scalar, PointVar)]) -> RistrettoPoint {
+        let fixed_base: RistrettoPoint = terms
+            .iter()
+            .filter_map(|(scalar, pt_var)| self.point_tables[pt_var.0].map(|table| table * scalar))
+            .sum();
+        let (mut scalars, points): (Vec<Scalar>, Vec<RistrettoPoint>) = terms
+            .iter()
+            .filter(|(_scalar, pt_var)| self.point_tables[pt_var.0].is_none())
+            .map(|(scalar, pt_var)| (*scalar, self.points[pt_var.0]))
+            .unzip();
+
+        let variable_base = RistrettoPoint::multiscalar_mul(&scalars, &points);
+        scalars.zeroize();
+
+        fixed_base + variable_base
+    }
+
+    /// The layout of the challenges and responses of the proof.
+    fn layout(&self) -> ProofLayout {
+        ProofLayout::new(self.constraints.iter().map(|(clause_nr, _lhs_var, rhs_lc)| {
+            (*clause_nr, rhs_lc.iter().map(|(sc_var, _pt_var)| sc_var.0))
+        }))
+    }
+
+    /// Choose the clause to prove; the other clauses are simulated.
+    ///
+    /// This is the clause set with [`Prover::set_real_clause`], or
+    /// else the first clause whose secrets are all assigned.  The
+    /// choice doesn't evaluate the constraints, so that its timing
+    /// doesn't depend on which of the assigned clauses hold.
+    fn choose_clause(&self, layout: &ProofLayout) -> Result<usize, ProofError> {
+        let assigned = |clause_nr: usize| {
+            layout
+                .response_slots()
+                .filter(|(slot_clause_nr, _scalar)| *slot_clause_nr == clause_nr)
+                .all(|(_clause_nr, scalar)| self.scalars[scalar].is_some())
+        };
+
+        match self.real_clause {
+            Some(clause_nr) if (1..=layout.num_challenges()).contains(&clause_nr) && assigned(clause_nr) => {
+                Ok(clause_nr)
+            }
+            Some(_) => Err(ProofError::InputMismatch),
+            None => (1..=layout.num_challenges())
+                .find(|clause_nr| assigned(*clause_nr))
+                .ok_or(ProofError::InputMismatch),
+        }
+    }
+
+    /// Generate the blinding factors of the proven clause, the
+    /// simulated responses and challenges of the other clauses, and
+    /// the commitments to the blinded LHS of each constraint.
+    ///
+    /// This doesn't depend on the transcript except to seed the
+    /// blinding factors, so it can be done ahead of time.
+    fn precompute<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        fresh_entropy: &[u8],
+    ) -> Result<Precommitment, ProofError> {
+        let layout = self.layout();
+        let real_clause = self.choose_clause(&layout)?;
+
+        // Construct a TranscriptRng
+        let mut rng_builder = self.transcript.build_rng();
+        for scalar in self.scalars.iter() {
+            if scalar.is_some() {
+                rng_builder = rng_builder.rekey_with_witness_bytes(b"", scalar.unwrap().as_bytes());
+            }
+        }
+        if !fresh_entropy.is_empty() {
+            rng_builder = rng_builder.rekey_with_witness_bytes(b"fresh", fresh_entropy);
+        }
+        let mut transcript_rng = rng_builder.finalize(rng);
+
+        // Generate a blinding factor for each response of the proven
+        // clause, and a simulated response for each other response
+        let mut blindings = Vec::with_capacity(layout.num_responses());
+        let mut fake_responses = Vec::with_capacity(layout.num_responses());
+        for (clause_nr, _scalar) in layout.response_slots() {
+            let random = Some(Scalar::random(&mut transcript_rng));
+            match clause_nr == real_clause {
+                true => {
+                    blindings.push(random);
+                    fake_responses.push(None);
+                }
+                false => {
+                    blindings.push(None);
+                    fake_responses.push(random);
+                }
+            }
+        }
+
+        // Simulate the challenge of each other clause
+        let shares = (1..=layout.num_challenges())
+            .map(|clause_nr| match clause_nr == real_clause {
+                true => None,
+                false => Some(Scalar::random(&mut transcript_rng)),
+            })
+            .collect::<Vec<Option<Scalar>>>();
+
+        // Commit to each blinded LHS
+        let commitments = self
+            .constraints
+            .iter()
+            .map(|(clause_nr, lhs, rhs_lc)| {
+                let response = |sc_var: &ScalarVar| layout.response_index(*clause_nr, sc_var.0);
+                let mut terms = match shares[clause_nr - 1] {
+                    None => rhs_lc
+                        .iter()
+                        .map(|(sc_var, pt_var)| (blindings[response(sc_var)].unwrap(), *pt_var))
+                        .collect::<Vec<_>>(),
+                    Some(challenge) => rhs_lc
+                        .iter()
+                        .map(|(sc_var, pt_var)| (fake_responses[response(sc_var)].unwrap(), *pt_var))
+                        .chain(lhs.iter().map(|(coeff, pt_var)| (-(challenge * coeff), *pt_var)))
+                        .collect::<Vec<_>>(),
+                };
+                let commitment = self.combine(&terms);
+                terms.iter_mut().for_each(|(scalar, _pt_var)| scalar.zeroize());
+                commitment
+            })
+            .collect();
+
+        Ok(Precommitment {
+            scalars: Vec::new(),
+            points: Vec::new(),
+            real_clause,
+            commitments,
+            blindings,
+            fake_responses,
+            known_chal_shares: shares,
+        })
+    }
+
+    /// Take the blinding factors from a precommitment, and feed its
+    /// commitments into the transcript.
+    fn apply_precommitment(&mut self, mut precommitment: Precommitment) {
+        let transcript = &mut self.transcript;
+        let point_labels = &self.point_labels;
+        self.commitments = precommitment
+            .commitments
+            .iter()
+            .zip(self.constraints.iter())
+            .map(|(commitment, (_clause_nr, lhs, _rhs_lc))| {
+                transcript.append_blinding_commitment(point_labels[lhs[0].1 .0], commitment)
+            })
+            .collect();
+        self.blindings = mem::take(&mut precommitment.blindings);
+        self.fake_responses = mem::take(&mut precommitment.fake_responses);
+        self.known_chal_shares = mem::take(&mut precommitment.known_chal_shares);
+    }
+
+    /// Consume this prover to perform the offline phase of proving,
+    /// producing a [`Precommitment`] for a later proof.
+    #[cfg(feature = "std")]
+    pub fn precommit(self) -> Result<Precommitment, ProofError> {
+        self.precommit_with_rng(&mut thread_rng())
+    }
+
+    /// Consume this prover to perform the offline phase of proving,
+    /// generating the blinding factors with randomness from `rng`.
+    ///
+    /// The prover should be set up with the same statement, secrets
+    /// and points as the prover which will use the precommitment, but
+    /// its transcript is only used to seed the blinding factors, so it
+    /// doesn't need to contain the context of the final proof.
+    ///
+    /// Unlike a proof, a precommitment isn't bound to the context of
+    /// the proof it's later used for, so `rng` is the only thing that
+    /// tells two precommitments for the same secrets apart. It must be
+    /// a fresh CSPRNG, never one seeded for reproducibility: two
+    /// precommitments with the same nonces used for two proofs leak
+    /// the secrets. With the `std` feature, fresh randomness from
+    /// `thread_rng()` is mixed in as well.
+    pub fn precommit_with_rng<R: RngCore + CryptoRng>(
+        self,
+        rng: &mut R,
+    ) -> Result<Precommitment, ProofError> {
+        #[cfg(feature = "std")]
+        let fresh_entropy = {
+            let mut bytes = [0u8; 32];
+            thread_rng().fill_bytes(&mut bytes);
+            bytes
         };
+        #[cfg(not(feature = "std"))]
+        let fresh_entropy = [0u8; 0];
+        let mut precommitment = self.precompute(rng, &fresh_entropy)?;
+        precommitment.scalars = self.scalars.to_vec();
+        precommitment.points = self.points.to_vec();
+        Ok(precommitment)
+    }
+
+    /// Consume this prover and a [`Precommitment`] to perform the
+    /// online phase of proving, producing a compact proof.
+    ///
+    /// Returns [`ProofError::PrecommitmentMismatch`] if the
+    /// precommitment was made for different points or secrets.
+    pub fn prove_compact_with_precommitment(
+        self,
+        precommitment: Precommitment,
+    ) -> Result<CompactProof, ProofError> {
+        let proof = self.prove_with_precommitment(precommitment)?;
+
+        Ok(CompactProof {
+            challenges: proof.challenges,
+            responses: proof.responses,
+        })
+    }
+
+    /// Consume this prover and a [`Precommitment`] to perform the
+    /// online phase of proving, producing a batchable proof.
+    ///
+    /// See [`Prover::prove_compact_with_precommitment`] for details.
+    pub fn prove_batchable_with_precommitment(
+        self,
+        precommitment: Precommitment,
+    ) -> Result<BatchableProof, ProofError> {
+        self.prove_with_precommitment(precommitment)
+    }
+
+    fn prove_with_precommitment(mut self, precommitment: Precommitment) -> Result<BatchableProof, ProofError> {
+        let layout = self.layout();
+        if precommitment.real_clause != self.choose_clause(&layout)?
+            || precommitment.blindings.len() != layout.num_responses()
+            || precommitment.known_chal_shares.len() != layout.num_challenges()
+            || precommitment.commitments.len() != self.constraints.len()
+            || precommitment.scalars[..] != self.scalars[..]
+            || precommitment.points[..] != self.points[..]
+        {
+            return Err(ProofError::PrecommitmentMismatch);
+        }
+
+        self.apply_precommitment(precommitment);
+        self.challenge();
+        self.response();
+        Ok(mem::take(&mut self.proof))
+    }
+
+    /// The compact and batchable proofs differ only by which data they store.
+    fn prove_impl<R: RngCore + CryptoRng>(mut self, rng: &mut R) -> Result<BatchableProof, ProofError> {
+        self.commit_with_rng(rng)?;
 
         // Obtain a scalar challenge and compute responses
         self.challenge();
         self.response();
-        Ok(self.proof)
+        Ok(mem::take(&mut self.proof))
     }
 
     /// Consume this prover to produce a compact proof.
+    #[cfg(feature = "std")]
     pub fn prove_compact(self) -> Result<CompactProof, ProofError> {
-        let result = self.prove_impl();
-        let proof = match result.is_ok() {
-            true => result.unwrap(),
-            false => return Err(result.err().unwrap())
-        };
+        self.prove_compact_with_rng(&mut thread_rng())
+    }
+
+    /// Consume this prover to produce a compact proof, using `rng`
+    /// to hedge the generation of the blinding factors.
+    ///
+    /// The blinding factors are derived from the transcript and the
+    /// secrets as well as from `rng`, so a deterministic `rng` gives
+    /// reproducible proofs (e.g., for test vectors) without making
+    /// them predictable to anyone who doesn't know the secrets.
+    pub fn prove_compact_with_rng<R: RngCore + CryptoRng>(
+        self,
+        rng: &mut R,
+    ) -> Result<CompactProof, ProofError> {
+        let proof = self.prove_impl(rng)?;
 
         Ok(CompactProof {
             challenges: proof.challenges,
@@ -120,94 +469,70 @@ impl<'a> Prover<'a> {
     }
 
     /// Consume this prover to produce a batchable proof.
+    #[cfg(feature = "std")]
     pub fn prove_batchable(self) -> Result<BatchableProof, ProofError> {
-        let result = self.prove_impl();
-        let proof = match result.is_ok() {
-            true => result.unwrap(),
-            false => return Err(result.err().unwrap())
-        };
-
+        self.prove_batchable_with_rng(&mut thread_rng())
+    }
 
-        Ok(proof)
+    /// Consume this prover to produce a batchable proof, using `rng`
+    /// to hedge the generation of the blinding factors.
+    ///
+    /// See [`Prover::prove_compact_with_rng`] for details.
+    pub fn prove_batchable_with_rng<R: RngCore + CryptoRng>(
+        self,
+        rng: &mut R,
+    ) -> Result<BatchableProof, ProofError> {
+        self.prove_impl(rng)
     }
 }
 
-impl<'a> IsSigmaProtocol for Prover<'a> {
-    type Proof = BatchableProof;
-
-    fn commit(&mut self) -> Result<(), ProofError> {
-        // Construct a TranscriptRng
-        let mut rng_builder = self.transcript.build_rng();
-        for scalar in &self.scalars {
-            if scalar.is_some() {
-                rng_builder = rng_builder.rekey_with_witness_bytes(b"", scalar.unwrap().as_bytes());
-            }
-        }
-        let mut transcript_rng = rng_builder.finalize(&mut thread_rng());
+/// The precomputed commitments and blinding factors for one proof,
+/// produced by the offline phase of proving.
+///
+/// A precommitment is produced by [`Prover::precommit`], and consumed
+/// by [`Prover::prove_compact_with_precommitment`] or
+/// [`Prover::prove_batchable_with_precommitment`].  Reusing blinding
+/// factors for two proofs would reveal the secrets, so precommitments
+/// can't be cloned or serialized, and are zeroized when dropped.
+///
+/// A precommitment keeps a copy of the secrets and points it was made
+/// for, to check that it is used with the same ones.
+pub struct Precommitment {
+    scalars: Vec<Option<Scalar>>,
+    points: Vec<RistrettoPoint>,
+    real_clause: usize,
+    commitments: Vec<RistrettoPoint>,
+    blindings: Vec<Option<Scalar>>,
+    fake_responses: Vec<Option<Scalar>>,
+    known_chal_shares: Vec<Option<Scalar>>,
+}
 
-        // Generate a blinding factor for each secret variable
-        let blindings = self
-            .scalars
-            .iter()
-            .map(|scalar| {
-                return match scalar.is_some() {
-                    true => Some(Scalar::random(&mut transcript_rng)),
-                    false => None
-                };
-            })
-            .collect::<Vec<Option<Scalar>>>();
+impl Drop for Precommitment {
+    fn drop(&mut self) {
+        self.scalars.zeroize();
+        self.blindings.zeroize();
+        self.fake_responses.zeroize();
+        self.known_chal_shares.zeroize();
+    }
+}
 
-        // Commit to each blinded LHS
-        let mut commitments = Vec::with_capacity(self.constraints.len());
-        let mut fake_responses = Vec::with_capacity(self.constraints.iter().map(|cs| &cs.1).count());
-        let mut shares = Vec::with_capacity(self.constraints.len());
-        let mut prev_clause_nr = 0;
-        for (clause_nr, lhs_var, rhs_lc) in &self.constraints {
-            let commitment = match blindings[(rhs_lc[0].0).0].is_some() {
-                true => {
-                    if prev_clause_nr == 0 {
-                        prev_clause_nr = *clause_nr;
-                    }
-                    if prev_clause_nr != *clause_nr {
-                        return Err(ProofError::InputMismatch);
-                    }
-                    shares.push(None);
-                    RistrettoPoint::multiscalar_mul(
-                        rhs_lc.iter().map(|(sc_var, _pt_var)| {
-                            fake_responses.push(None);
-                            blindings[sc_var.0].unwrap()
-                        }),
-                        rhs_lc.iter().map(|(_sc_var, pt_var)| self.points[pt_var.0]),
-                    )
-                }
-                false => {
-                    let challenge = Scalar::random(&mut transcript_rng);
-                    shares.push(Some(challenge));
-                    RistrettoPoint::multiscalar_mul(
-                        rhs_lc.iter()
-                            .map(|(_sc_var, _pt_var)| {
-                                let response = Scalar::random(&mut transcript_rng);
-                                fake_responses.push(Some(response));
-                                response
-                            })
-                            .chain(iter::once(-&challenge)),
-                        rhs_lc.iter()
-                            .map(|(_sc_var, pt_var)| self.points[pt_var.0])
-                            .chain(iter::once(self.points[lhs_var.0])),
-                    )
-                }
-            };
+impl<'a> Drop for Prover<'a> {
+    fn drop(&mut self) {
+        if let Cow::Owned(scalars) = &mut self.scalars {
+            scalars.zeroize();
+        }
+        self.blindings.zeroize();
+        self.fake_responses.zeroize();
+        self.known_chal_shares.zeroize();
+    }
+}
 
-            let encoding = self
-                .transcript
-                .append_blinding_commitment(self.point_labels[lhs_var.0], &commitment);
+impl<'a> IsSigmaProtocol for Prover<'a> {
+    type Proof = BatchableProof;
 
-            commitments.push(encoding);
-        }
-        self.blindings = blindings;
-        self.commitments = commitments;
-        self.fake_responses = fake_responses;
-        self.known_chal_shares = shares;
+    fn commit_with_rng<R: RngCore + CryptoRng>(&mut self, rng: &mut R) -> Result<(), ProofError> {
+        let precommitment = self.precompute(rng, &[])?;
+        self.apply_precommitment(precommitment);
         Ok(())
     }
 
@@ -216,23 +541,19 @@ impl<'a> IsSigmaProtocol for Prover<'a> {
     }
 
     fn response(&mut self) {
-        let challenges = SecretShare::complete(self.challenge, &mut self.known_chal_shares).unwrap();
-        let blindings = &self.blindings;
-        let fake_responses = &self.fake_responses;
-        let responses = self.scalars.iter().zip(blindings)
-            .zip(fake_responses)
-            .zip(&challenges.shares[1..])
-            .map(|(scBlResp, ch)| {
-                match scBlResp.1.is_some() {
-                    true => scBlResp.1.unwrap(),
-                    false => (scBlResp.0).0.unwrap() * ch + (scBlResp.0).1.unwrap()
-                }
+        let challenges = SecretShare::complete(self.challenge, &self.known_chal_shares).unwrap();
+        let responses = self
+            .layout()
+            .response_slots()
+            .zip(self.blindings.iter().zip(self.fake_responses.iter()))
+            .map(|((clause_nr, scalar), (blinding, fake_response))| match blinding {
+                Some(blinding) => self.scalars[scalar].unwrap() * challenges.shares[clause_nr - 1] + blinding,
+                None => fake_response.unwrap(),
             })
             .collect::<Vec<Scalar>>();
-        let out_shares = challenges.shares;
         let commitments = self.commitments.clone();
-        self.proof = BatchableProof{
-            challenges: out_shares,
+        self.proof = BatchableProof {
+            challenges: challenges.shares,
             responses,
             commitments,
         };
@@ -244,11 +565,21 @@ impl<'a> SchnorrCS for Prover<'a> {
     type PointVar = PointVar;
     type SubroutineVar = Prover<'a>;
 
-    fn constrain(&mut self, clause_nr: usize, lhs: PointVar, linear_combination: Vec<(ScalarVar, PointVar)>) {
-        self.constraints.push((clause_nr, lhs, linear_combination));
+    fn constrain_combination(
+        &mut self,
+        lhs: Vec<(Scalar, PointVar)>,
+        linear_combination: Vec<(ScalarVar, PointVar)>,
+    ) {
+        check_lhs(&lhs);
+        let clause_nr = self.clauses.next_constraint();
+        self.constraints.to_mut().push((clause_nr, lhs, linear_combination));
     }
 
     fn add_subroutine(&mut self, subroutine: Prover<'a>) {
         self.subroutines.push(subroutine);
     }
+
+    fn clauses(&mut self) -> &mut Clauses {
+        &mut self.clauses
+    }
 }
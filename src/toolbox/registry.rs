@@ -0,0 +1,133 @@
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+
+use crate::toolbox::Statement;
+use crate::{ProofEnvelope, ProofError, Transcript};
+
+/// Verifies the proof in an envelope for one registered statement.
+type EnvelopeVerifier = Box<dyn Fn(&ProofEnvelope, &mut Transcript) -> Result<(), ProofError> + Send + Sync>;
+
+/// A registered statement: its description and its verifier.
+struct Registration {
+    description: String,
+    verifier: EnvelopeVerifier,
+}
+
+/// A set of proof statements, which verifies each [`ProofEnvelope`]
+/// with the statement it is labeled with.
+///
+/// To use a [`StatementRegistry`], first construct one using
+/// [`StatementRegistry::new()`], and register each supported
+/// statement with [`StatementRegistry::register`], together with the
+/// common points the verifier expects for it.  Statements which are
+/// only known at runtime, and so have no [`Statement`]
+/// implementation, can be registered with a verification function
+/// using [`StatementRegistry::register_fn`].  Then, use
+/// [`StatementRegistry::verify_envelope`] or
+/// [`StatementRegistry::verify_envelope_bytes`] to verify proofs of
+/// any of the registered statements, and
+/// [`StatementRegistry::statements`] to list them.
+#[derive(Default)]
+pub struct StatementRegistry {
+    statements: BTreeMap<String, Registration>,
+}
+
+impl StatementRegistry {
+    /// Construct a new, empty, registry.
+    pub fn new() -> Self {
+        StatementRegistry::default()
+    }
+
+    /// Register the statement `S` under its `PROOF_LABEL`, with the
+    /// `common` points expected for its proofs.
+    ///
+    /// The statement is described by its `DESCRIPTION`.
+    ///
+    /// # Panics
+    ///
+    /// If a statement with the same label is already registered.
+    pub fn register<S>(&mut self, common: S::CommonPoints)
+    where
+        S: Statement,
+        S::CommonPoints: Send + Sync + 'static,
+    {
+        self.insert(
+            S::PROOF_LABEL,
+            S::DESCRIPTION,
+            Box::new(move |envelope, transcript| S::verify_envelope(envelope, transcript, &common)),
+        );
+    }
+
+    /// Register a statement under its `label`, with a `description`
+    /// and a `verifier` function checking the proof in an envelope.
+    ///
+    /// The `verifier` is only called with envelopes labeled with
+    /// `label`.  It is responsible for checking the number of
+    /// instance points, and should use `label` as the domain
+    /// separator of the proof, as the constraint system API does with
+    /// the label passed to
+    /// [`Verifier::new`](crate::toolbox::verifier::Verifier::new).
+    ///
+    /// # Panics
+    ///
+    /// If a statement with the same label is already registered.
+    pub fn register_fn<F>(&mut self, label: &str, description: &str, verifier: F)
+    where
+        F: Fn(&ProofEnvelope, &mut Transcript) -> Result<(), ProofError> + Send + Sync + 'static,
+    {
+        self.insert(label, description, Box::new(verifier));
+    }
+
+    fn insert(&mut self, label: &str, description: &str, verifier: EnvelopeVerifier) {
+        let registration = Registration {
+            description: String::from(description),
+            verifier,
+        };
+        let previous = self.statements.insert(String::from(label), registration);
+        assert!(previous.is_none(), "statement {:?} is already registered", label);
+    }
+
+    /// Whether a statement is registered with the given `label`.
+    pub fn contains(&self, label: &str) -> bool {
+        self.statements.contains_key(label)
+    }
+
+    /// The description of the statement registered with the given
+    /// `label`, if any.
+    pub fn description(&self, label: &str) -> Option<&str> {
+        self.statements
+            .get(label)
+            .map(|registration| registration.description.as_str())
+    }
+
+    /// The labels and descriptions of the registered statements,
+    /// ordered by label.
+    pub fn statements(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.statements
+            .iter()
+            .map(|(label, registration)| (label.as_str(), registration.description.as_str()))
+    }
+
+    /// Verify the proof in an `envelope` with the registered statement
+    /// it is labeled with.
+    ///
+    /// Returns [`ProofError::UnknownStatement`] if no statement is
+    /// registered with its label.
+    pub fn verify_envelope(&self, envelope: &ProofEnvelope, transcript: &mut Transcript) -> Result<(), ProofError> {
+        let registration = self
+            .statements
+            .get(&envelope.label)
+            .ok_or_else(|| ProofError::UnknownStatement {
+                label: envelope.label.clone(),
+            })?;
+
+        (registration.verifier)(envelope, transcript)
+    }
+
+    /// Decode an envelope from its canonical encoding, and verify it
+    /// like [`StatementRegistry::verify_envelope`].
+    pub fn verify_envelope_bytes(&self, bytes: &[u8], transcript: &mut Transcript) -> Result<(), ProofError> {
+        self.verify_envelope(&ProofEnvelope::from_bytes(bytes)?, transcript)
+    }
+}
@@ -1,31 +1,40 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
 use curve25519_dalek::scalar::Scalar;
 
+/// A sharing of a secret scalar into shares which add up to it.
+///
+/// All but one of the shares can be chosen freely, and the remaining
+/// share is determined by the secret.  This is how the challenge of a
+/// proof is split among the clauses of a statement: the prover picks
+/// the challenges of the clauses it simulates, and the challenge of
+/// the clause it proves is whatever is left.
 #[derive(Clone)]
 pub struct SecretShare {
-    secret: Scalar,
+    /// The shares, in order.
     pub shares: Vec<Scalar>,
-    threshold: usize,
 }
 
 impl SecretShare {
-    pub fn complete(secret: Scalar, shares: &mut Vec<Option<Scalar>>) -> Result<SecretShare, String> {
-        let nr_of_shares = shares.iter().filter(|&n| n.is_some()).count();
+    /// Complete the known `shares` of the `secret` by filling in the
+    /// unknown one, which must be the only one.
+    pub fn complete(secret: Scalar, shares: &[Option<Scalar>]) -> Result<SecretShare, String> {
+        let nr_of_unknown = shares.iter().filter(|share| share.is_none()).count();
+        if nr_of_unknown != 1 {
+            return Err(String::from("exactly one share must be unknown"));
+        }
 
-        let mut output: Vec<Scalar> = shares.iter().map(|share| {
-            return match share.is_some() {
-                true => share.unwrap(),
-                false => secret //TODO: Shamir Secret Sharing
-            };
-        }).collect();
-        output.insert(0, secret);
-        Ok(SecretShare {
-            secret,
-            shares: output,
-            threshold: nr_of_shares,
-        })
+        let known: Scalar = shares.iter().flatten().sum();
+        let shares = shares
+            .iter()
+            .map(|share| share.unwrap_or(secret - known))
+            .collect();
+        Ok(SecretShare { shares })
     }
 
-    pub fn reconstruct(shares: Vec<Scalar>) -> Result<Scalar, String> {
-        return Ok(shares[0]); //TODO Shamir Secret Reconstruction
+    /// Reconstruct the secret from all of its `shares`.
+    pub fn reconstruct(shares: &[Scalar]) -> Scalar {
+        shares.iter().sum()
     }
-}
\ No newline at end of file
+}
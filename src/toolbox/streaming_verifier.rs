@@ -0,0 +1,161 @@
+#[cfg(feature = "std")]
+use rand::thread_rng;
+use rand::{CryptoRng, RngCore};
+
+use alloc::vec::Vec;
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+
+use crate::toolbox::prepared_verifier::PreparedStatement;
+use crate::{BatchableProof, ProofError, Transcript};
+
+/// Used to batch-verify an unbounded stream of proofs of one
+/// statement in bounded memory.
+///
+/// To use a [`StreamingVerifier`], first prepare the statement with a
+/// [`PreparedVerifier`](crate::toolbox::prepared_verifier::PreparedVerifier),
+/// then construct one using [`StreamingVerifier::new()`], supplying
+/// the [`PreparedStatement`] and a chunk size.
+///
+/// Then, pass each proof to [`StreamingVerifier::push`] together with
+/// its transcript and the assignments to its instance points.  The
+/// transcript is processed immediately, so it doesn't need to outlive
+/// the call.  Proofs are accumulated until a chunk is full, and the
+/// chunk is then checked with a single multiscalar multiplication, so
+/// at most one chunk of proofs is held at a time.
+///
+/// Finally, use [`StreamingVerifier::finish`] to check the last,
+/// partial chunk, and obtain the result for the whole stream.
+pub struct StreamingVerifier<'s> {
+    statement: &'s PreparedStatement,
+    chunk_size: usize,
+    /// The proofs waiting to be checked, with their index in the stream.
+    chunk: Vec<(usize, BatchableProof, Vec<CompressedRistretto>)>,
+    num_proofs: usize,
+    first_error: Option<ProofError>,
+}
+
+impl<'s> StreamingVerifier<'s> {
+    /// Construct a streaming verifier for proofs of the prepared
+    /// `statement`, which checks the proofs in chunks of `chunk_size`.
+    pub fn new(statement: &'s PreparedStatement, chunk_size: usize) -> Self {
+        let chunk_size = chunk_size.max(1);
+        StreamingVerifier {
+            statement,
+            chunk_size,
+            chunk: Vec::with_capacity(chunk_size),
+            num_proofs: 0,
+            first_error: None,
+        }
+    }
+
+    /// The number of proofs added to the stream so far.
+    pub fn len(&self) -> usize {
+        self.num_proofs
+    }
+
+    /// Whether no proofs have been added to the stream.
+    pub fn is_empty(&self) -> bool {
+        self.num_proofs == 0
+    }
+
+    /// Add a proof to the stream, given its transcript and the
+    /// assignments to its instance points.
+    ///
+    /// Returns an error if the proof is malformed, or if it completes
+    /// a chunk which fails verification.  The error is also recorded
+    /// in the result of [`StreamingVerifier::finish`].
+    #[cfg(feature = "std")]
+    pub fn push(
+        &mut self,
+        proof: &BatchableProof,
+        transcript: &mut Transcript,
+        instance_points: &[CompressedRistretto],
+    ) -> Result<(), ProofError> {
+        self.push_with_rng(proof, transcript, instance_points, &mut thread_rng())
+    }
+
+    /// Like [`StreamingVerifier::push`], but draws the random factors
+    /// used to check a completed chunk from `rng`.
+    pub fn push_with_rng<R: RngCore + CryptoRng>(
+        &mut self,
+        proof: &BatchableProof,
+        transcript: &mut Transcript,
+        instance_points: &[CompressedRistretto],
+        rng: &mut R,
+    ) -> Result<(), ProofError> {
+        let index = self.num_proofs;
+        self.num_proofs += 1;
+
+        let result = self
+            .statement
+            .append_proof(transcript, instance_points, proof)
+            .map_err(|e| e.in_batch(index));
+        let result = match result {
+            Ok(()) => {
+                self.chunk.push((index, proof.clone(), instance_points.to_vec()));
+                if self.chunk.len() == self.chunk_size {
+                    self.check_chunk(rng)
+                } else {
+                    Ok(())
+                }
+            }
+            Err(e) => Err(e),
+        };
+
+        self.record(result)
+    }
+
+    /// Consume the verifier to check the remaining proofs, and produce
+    /// a verification result for the whole stream.
+    ///
+    /// If several proofs or chunks failed, the first error is returned.
+    #[cfg(feature = "std")]
+    pub fn finish(self) -> Result<(), ProofError> {
+        self.finish_with_rng(&mut thread_rng())
+    }
+
+    /// Like [`StreamingVerifier::finish`], but draws the random factors
+    /// used to check the remaining proofs from `rng`.
+    pub fn finish_with_rng<R: RngCore + CryptoRng>(mut self, rng: &mut R) -> Result<(), ProofError> {
+        if let Err(e) = self.check_chunk(rng) {
+            self.first_error.get_or_insert(e);
+        }
+
+        match self.first_error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// Check the proofs in the current chunk and start a new one.
+    ///
+    /// Errors about a single proof are reported with its index in the
+    /// stream.
+    fn check_chunk<R: RngCore + CryptoRng>(&mut self, rng: &mut R) -> Result<(), ProofError> {
+        if self.chunk.is_empty() {
+            return Ok(());
+        }
+
+        let batch = self
+            .chunk
+            .iter()
+            .map(|(_index, proof, points)| (proof, &points[..]))
+            .collect::<Vec<_>>();
+        let result = self.statement.check(&batch, rng).map_err(|e| match e {
+            ProofError::BatchProofFailure { index, source } => (*source).in_batch(self.chunk[index].0),
+            e => e,
+        });
+        self.chunk.clear();
+
+        result
+    }
+
+    /// Record the first error in the stream.
+    fn record(&mut self, result: Result<(), ProofError>) -> Result<(), ProofError> {
+        if let Err(ref e) = result {
+            self.first_error.get_or_insert_with(|| e.clone());
+        }
+        result
+    }
+}
@@ -1,11 +1,17 @@
-use rand::{thread_rng, Rng};
-use std::iter;
+#[cfg(feature = "std")]
+use rand::thread_rng;
+use rand::{CryptoRng, Rng, RngCore};
+
+use alloc::vec::Vec;
+use core::iter;
 
 use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
 use curve25519_dalek::scalar::Scalar;
 use curve25519_dalek::traits::{IsIdentity, VartimeMultiscalarMul};
 
-use crate::toolbox::{SchnorrCS, TranscriptProtocol};
+use crate::toolbox::multi_batch_verifier::Equation;
+use crate::toolbox::{check_lhs, normalize_constraints, Clauses, ProofLayout, SchnorrCS, TranscriptProtocol};
+use crate::errors::label_string;
 use crate::{BatchableProof, CompactProof, ProofError, Transcript};
 use crate::toolbox::shamir_secrets::SecretShare;
 
@@ -23,13 +29,15 @@ use crate::toolbox::shamir_secrets::SecretShare;
 ///
 /// Finally, use [`Verifier::verify_compact`] or
 /// [`Verifier::verify_batchable`] to consume the verifier and produce
-/// a verification result.
+/// a verification result, or [`Verifier::compact_from_batchable`] to
+/// check a batchable proof and convert it to a compact proof.
 pub struct Verifier<'a> {
     transcript: &'a mut Transcript,
     num_scalars: usize,
     points: Vec<CompressedRistretto>,
-    point_labels: Vec<&'static [u8]>,
-    constraints: Vec<(PointVar, Vec<(ScalarVar, PointVar)>)>,
+    point_labels: Vec<&'a [u8]>,
+    constraints: Vec<Constraint>,
+    clauses: Clauses,
     subroutines: Vec<Verifier<'a>>,
 }
 
@@ -37,16 +45,19 @@ pub struct Verifier<'a> {
 ///
 /// Note that this variable is only a placeholder; it has no
 /// assignment, because the verifier doesn't know the secrets.
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
 pub struct ScalarVar(usize);
 /// A public variable used during verification.
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
 pub struct PointVar(usize);
 
+/// A constraint `lhs = linear_combination`, tagged with its clause number.
+type Constraint = (usize, Vec<(Scalar, PointVar)>, Vec<(ScalarVar, PointVar)>);
+
 impl<'a> Verifier<'a> {
     /// Construct a verifier for the proof statement with the given
     /// `proof_label`, operating on the given `transcript`.
-    pub fn new(proof_label: &'static [u8], transcript: &'a mut Transcript) -> Self {
+    pub fn new(proof_label: &[u8], transcript: &'a mut Transcript) -> Self {
         transcript.domain_sep(proof_label);
         Verifier {
             transcript,
@@ -54,12 +65,13 @@ impl<'a> Verifier<'a> {
             points: Vec::default(),
             point_labels: Vec::default(),
             constraints: Vec::default(),
+            clauses: Clauses::default(),
             subroutines: Vec::default(),
         }
     }
 
     /// Allocate a placeholder scalar variable, without an assignment.
-    pub fn allocate_scalar(&mut self, label: &'static [u8]) -> ScalarVar {
+    pub fn allocate_scalar(&mut self, label: &[u8]) -> ScalarVar {
         self.transcript.append_scalar_var(label);
         self.num_scalars += 1;
         ScalarVar(self.num_scalars - 1)
@@ -69,7 +81,7 @@ impl<'a> Verifier<'a> {
     /// the assignment is invalid.
     pub fn allocate_point(
         &mut self,
-        label: &'static [u8],
+        label: &'a [u8],
         assignment: CompressedRistretto,
     ) -> Result<PointVar, ProofError> {
         self.transcript
@@ -79,95 +91,142 @@ impl<'a> Verifier<'a> {
         Ok(PointVar(self.points.len() - 1))
     }
 
+    /// Normalize the statement, removing duplicate constraints and
+    /// ordering the terms of each constraint, as the prover did with
+    /// [`Prover::normalize`](crate::toolbox::prover::Prover::normalize).
+    pub fn normalize(&mut self) {
+        normalize_constraints(&mut self.constraints);
+    }
+
+    /// The layout of the challenges and responses of the proof.
+    fn layout(&self) -> ProofLayout {
+        ProofLayout::new(self.constraints.iter().map(|(clause_nr, _lhs_var, rhs_lc)| {
+            (*clause_nr, rhs_lc.iter().map(|(sc_var, _pt_var)| sc_var.0))
+        }))
+    }
+
     /// Consume the verifier to produce a verification of a [`CompactProof`].
     pub fn verify_compact(self, proof: &CompactProof) -> Result<(), ProofError> {
-        // Check that there are as many responses as secret variables
-        if proof.responses.len() != self.num_scalars {
-            return Err(ProofError::VerificationFailure);
-        }
-
-        // Decompress all parameters or fail verification.
-        let points = self
-            .points
-            .iter()
-            .map(|pt| pt.decompress())
-            .collect::<Option<Vec<RistrettoPoint>>>()
-            .ok_or(ProofError::VerificationFailure)?;
-
-        // Recompute the prover's commitments based on their claimed challenge value:
-        for (index, (lhs_var, rhs_lc)) in self.constraints.iter().enumerate() {
-            let minus_c = -proof.challenges[index+1];
-            let commitment = RistrettoPoint::vartime_multiscalar_mul(
-                rhs_lc
-                    .iter()
-                    .map(|(sc_var, _pt_var)| {
-                        proof.responses[sc_var.0]
-                    })
-                    .chain(iter::once(minus_c)),
-                rhs_lc
-                    .iter()
-                    .map(|(_sc_var, pt_var)| points[pt_var.0])
-                    .chain(iter::once(points[lhs_var.0])),
-            );
+        let layout = self.layout();
+        layout.check_sizes(&proof.challenges, &proof.responses)?;
 
+        // Recompute the prover's commitments based on their claimed challenge values:
+        let commitments = self.recompute_commitments(&layout, &proof.responses, &proof.challenges)?;
+        for (commitment, (_clause_nr, lhs, _rhs_lc)) in commitments.iter().zip(self.constraints.iter()) {
             self.transcript
-                .append_blinding_commitment(self.point_labels[lhs_var.0], &commitment);
+                .append_blinding_commitment(self.point_labels[lhs[0].1 .0], commitment);
         }
 
         // Recompute the challenge and check if it's the claimed one
         let challenge = self.transcript.get_challenge(b"chal");
-        let rec_challenge = SecretShare::reconstruct(proof.clone().challenges);
 
-        if rec_challenge.is_ok() && challenge == rec_challenge.unwrap() {
+        if challenge == SecretShare::reconstruct(&proof.challenges) {
             Ok(())
         } else {
-            Err(ProofError::VerificationFailure)
+            Err(ProofError::ChallengeMismatch)
         }
     }
 
-    /// Consume the verifier to produce a verification of a [`BatchableProof`].
-    pub fn verify_batchable(self, proof: &BatchableProof) -> Result<(), ProofError> {
-        // Check that there are as many responses as secret variables
-        if proof.responses.len() != self.num_scalars {
-            return Err(ProofError::VerificationFailure);
-        }
-        // Check that there are as many commitments as constraints
-        if proof.commitments.len() != self.constraints.len() {
-            return Err(ProofError::VerificationFailure);
-        }
+    /// Decompress the points and recompute the prover's commitment
+    /// for each constraint from the responses and challenges.
+    fn recompute_commitments(
+        &self,
+        layout: &ProofLayout,
+        responses: &[Scalar],
+        challenges: &[Scalar],
+    ) -> Result<Vec<RistrettoPoint>, ProofError> {
+        // Decompress all parameters or fail verification.
+        let points = self
+            .points
+            .iter()
+            .zip(self.point_labels.iter())
+            .map(|(pt, label)| {
+                pt.decompress().ok_or_else(|| ProofError::InvalidPoint {
+                    label: label_string(label),
+                })
+            })
+            .collect::<Result<Vec<RistrettoPoint>, ProofError>>()?;
 
-        // Feed the prover's commitments into the transcript:
-        for (i, commitment) in proof.commitments.iter().enumerate() {
-            let (ref lhs_var, ref _rhs_lc) = self.constraints[i];
-            self.transcript.validate_and_append_blinding_commitment(
-                self.point_labels[lhs_var.0],
-                &commitment,
-            )?;
-        }
+        Ok(self
+            .constraints
+            .iter()
+            .map(|(clause_nr, lhs, rhs_lc)| {
+                let minus_c = -challenges[clause_nr - 1];
+                RistrettoPoint::vartime_multiscalar_mul(
+                    rhs_lc
+                        .iter()
+                        .map(|(sc_var, _pt_var)| responses[layout.response_index(*clause_nr, sc_var.0)])
+                        .chain(lhs.iter().map(|(coeff, _pt_var)| minus_c * coeff)),
+                    rhs_lc
+                        .iter()
+                        .map(|(_sc_var, pt_var)| points[pt_var.0])
+                        .chain(lhs.iter().map(|(_coeff, pt_var)| points[pt_var.0])),
+                )
+            })
+            .collect())
+    }
 
-        let challenge = self.transcript.get_challenge(b"chal");
-        let rec_challenge = SecretShare::reconstruct(proof.clone().challenges);
+    /// Consume the verifier to convert a [`BatchableProof`] into the
+    /// [`CompactProof`] for the same statement and transcript.
+    ///
+    /// This replays the transcript and checks each commitment exactly,
+    /// so it fails if and only if the batchable proof doesn't verify,
+    /// and the compact proof verifies if it succeeds.
+    pub fn compact_from_batchable(mut self, proof: &BatchableProof) -> Result<CompactProof, ProofError> {
+        let layout = self.append_batchable_proof(proof)?;
 
-        if rec_challenge.is_ok() && challenge != rec_challenge.unwrap() {
-            return Err(ProofError::VerificationFailure);
+        let commitments = self.recompute_commitments(&layout, &proof.responses, &proof.challenges)?;
+        for (i, (commitment, claimed)) in commitments.iter().zip(proof.commitments.iter()).enumerate() {
+            if commitment.compress() != *claimed {
+                return Err(if claimed.decompress().is_none() {
+                    ProofError::InvalidCommitment { constraint: i }
+                } else {
+                    ProofError::VerificationFailure
+                });
+            }
         }
 
+        Ok(CompactProof {
+            challenges: proof.challenges.clone(),
+            responses: proof.responses.clone(),
+        })
+    }
+
+    /// Consume the verifier to produce a verification of a [`BatchableProof`].
+    #[cfg(feature = "std")]
+    pub fn verify_batchable(self, proof: &BatchableProof) -> Result<(), ProofError> {
+        self.verify_batchable_with_rng(proof, &mut thread_rng())
+    }
+
+    /// Consume the verifier to produce a verification of a
+    /// [`BatchableProof`], drawing the random factors used to combine
+    /// the verification equations from `rng`.
+    pub fn verify_batchable_with_rng<R: RngCore + CryptoRng>(
+        mut self,
+        proof: &BatchableProof,
+        rng: &mut R,
+    ) -> Result<(), ProofError> {
+        let layout = self.append_batchable_proof(proof)?;
+
         let commitments_offset = self.points.len();
         let combined_points = self.points.iter().chain(proof.commitments.iter());
 
         let mut coeffs = vec![Scalar::zero(); self.points.len() + proof.commitments.len()];
-        // For each constraint of the form Q = sum(P_i, x_i),
-        // we want to ensure Q_com = sum(P_i, resp_i) - c * Q,
-        // so add the check rand*( sum(P_i, resp_i) - c * Q - Q_com ) == 0
+        // For each constraint of the form sum(Q_j, a_j) = sum(P_i, x_i),
+        // we want to ensure Q_com = sum(P_i, resp_i) - c * sum(Q_j, a_j),
+        // so add the check rand*( sum(P_i, resp_i) - c * sum(Q_j, a_j) - Q_com ) == 0
         for i in 0..self.constraints.len() {
-            let (ref lhs_var, ref rhs_lc) = self.constraints[i];
-            let random_factor = Scalar::from(thread_rng().gen::<u128>());
-            let minus_c = -proof.challenges[i+1];
+            let (clause_nr, ref lhs, ref rhs_lc) = self.constraints[i];
+            let random_factor = Scalar::from(rng.gen::<u128>());
+            let minus_c = -proof.challenges[clause_nr - 1];
 
             coeffs[commitments_offset + i] += -random_factor;
-            coeffs[lhs_var.0] += random_factor * minus_c;
+            for (coeff, pt_var) in lhs {
+                coeffs[pt_var.0] += random_factor * minus_c * coeff;
+            }
             for (sc_var, pt_var) in rhs_lc {
-                coeffs[pt_var.0] += random_factor * proof.responses[sc_var.0];
+                let response = proof.responses[layout.response_index(clause_nr, sc_var.0)];
+                coeffs[pt_var.0] += random_factor * response;
             }
         }
 
@@ -175,7 +234,7 @@ impl<'a> Verifier<'a> {
             &coeffs,
             combined_points.map(|pt| pt.decompress()),
         )
-        .ok_or(ProofError::VerificationFailure)?;
+        .ok_or_else(|| self.decompression_error(&proof.commitments))?;
 
         if check.is_identity() {
             Ok(())
@@ -183,6 +242,82 @@ impl<'a> Verifier<'a> {
             Err(ProofError::VerificationFailure)
         }
     }
+
+    /// Produce the verification equations for a [`BatchableProof`],
+    /// one per constraint, without checking them.
+    ///
+    /// Each equation is a list of terms whose sum must be the identity.
+    pub(crate) fn batch_equations(&mut self, proof: &BatchableProof) -> Result<Vec<Equation>, ProofError> {
+        let layout = self.append_batchable_proof(proof)?;
+
+        // For each constraint of the form sum(Q_j, a_j) = sum(P_i, x_i),
+        // we want to ensure sum(P_i, resp_i) - c * sum(Q_j, a_j) - Q_com == 0
+        Ok(self
+            .constraints
+            .iter()
+            .zip(proof.commitments.iter())
+            .map(|((clause_nr, lhs, rhs_lc), commitment)| {
+                let challenge = proof.challenges[clause_nr - 1];
+                rhs_lc
+                    .iter()
+                    .map(|(sc_var, pt_var)| {
+                        let response = proof.responses[layout.response_index(*clause_nr, sc_var.0)];
+                        (response, self.points[pt_var.0])
+                    })
+                    .chain(lhs.iter().map(|(coeff, pt_var)| (-(challenge * coeff), self.points[pt_var.0])))
+                    .chain(iter::once((-Scalar::one(), *commitment)))
+                    .collect()
+            })
+            .collect())
+    }
+
+    /// Check the sizes of a [`BatchableProof`], feed its commitments
+    /// into the transcript, and check the claimed challenges, returning
+    /// the layout of the proof.
+    fn append_batchable_proof(&mut self, proof: &BatchableProof) -> Result<ProofLayout, ProofError> {
+        let layout = self.layout();
+        layout.check_sizes(&proof.challenges, &proof.responses)?;
+        // Check that there are as many commitments as constraints
+        if proof.commitments.len() != self.constraints.len() {
+            return Err(ProofError::WrongCommitmentCount {
+                expected: self.constraints.len(),
+                found: proof.commitments.len(),
+            });
+        }
+
+        // Feed the prover's commitments into the transcript:
+        for (i, commitment) in proof.commitments.iter().enumerate() {
+            let (_clause_nr, ref lhs, ref _rhs_lc) = self.constraints[i];
+            self.transcript
+                .validate_and_append_blinding_commitment(self.point_labels[lhs[0].1 .0], commitment)
+                .map_err(|_| ProofError::InvalidCommitment { constraint: i })?;
+        }
+
+        let challenge = self.transcript.get_challenge(b"chal");
+        if challenge != SecretShare::reconstruct(&proof.challenges) {
+            return Err(ProofError::ChallengeMismatch);
+        }
+
+        Ok(layout)
+    }
+
+    /// Find the first point which fails to decompress, to report
+    /// which one made the verification check fail.
+    pub(crate) fn decompression_error(&self, commitments: &[CompressedRistretto]) -> ProofError {
+        for (point, label) in self.points.iter().zip(self.point_labels.iter()) {
+            if point.decompress().is_none() {
+                return ProofError::InvalidPoint {
+                    label: label_string(label),
+                };
+            }
+        }
+        for (constraint, commitment) in commitments.iter().enumerate() {
+            if commitment.decompress().is_none() {
+                return ProofError::InvalidCommitment { constraint };
+            }
+        }
+        ProofError::VerificationFailure
+    }
 }
 
 impl<'a> SchnorrCS for Verifier<'a> {
@@ -190,11 +325,21 @@ impl<'a> SchnorrCS for Verifier<'a> {
     type PointVar = PointVar;
     type SubroutineVar = Verifier<'a>;
 
-    fn constrain(&mut self, _clause_nr: usize, lhs: PointVar, linear_combination: Vec<(ScalarVar, PointVar)>) {
-        self.constraints.push((lhs, linear_combination));
+    fn constrain_combination(
+        &mut self,
+        lhs: Vec<(Scalar, PointVar)>,
+        linear_combination: Vec<(ScalarVar, PointVar)>,
+    ) {
+        check_lhs(&lhs);
+        let clause_nr = self.clauses.next_constraint();
+        self.constraints.push((clause_nr, lhs, linear_combination));
     }
 
     fn add_subroutine(&mut self, subroutine: Verifier<'a>) {
         self.subroutines.push(subroutine);
     }
+
+    fn clauses(&mut self) -> &mut Clauses {
+        &mut self.clauses
+    }
 }
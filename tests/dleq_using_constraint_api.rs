@@ -33,8 +33,8 @@ fn dleq_statement<CS: SchnorrCS>(
     B: CS::PointVar,
     H: CS::PointVar,
 ) {
-    cs.constrain(1, A, vec![(x, B)]);
-    cs.constrain(1, G, vec![(x, H)]);
+    cs.constrain(A, vec![(x, B)]);
+    cs.constrain(G, vec![(x, H)]);
 }
 
 #[test]
@@ -168,3 +168,317 @@ fn create_and_batch_verify_batchable_dleq() {
 
     assert!(verifier.verify_batchable(&proofs).is_ok());
 }
+
+#[test]
+fn verification_errors_name_the_cause() {
+    use curve25519_dalek::ristretto::CompressedRistretto;
+    use zkp::ProofError;
+
+    let B = dalek_constants::RISTRETTO_BASEPOINT_POINT;
+    let H = RistrettoPoint::hash_from_bytes::<Sha512>(B.compress().as_bytes());
+
+    let x = Scalar::from(89327492234u64);
+    let A = B * x;
+    let G = H * x;
+
+    let mut transcript = Transcript::new(b"DLEQTest");
+    let mut prover = Prover::new(b"DLEQProof", &mut transcript);
+    let var_x = prover.allocate_scalar(b"x", Some(x));
+    let (var_B, _) = prover.allocate_point(b"B", B);
+    let (var_H, _) = prover.allocate_point(b"H", H);
+    let (var_A, cmpr_A) = prover.allocate_point(b"A", A);
+    let (var_G, cmpr_G) = prover.allocate_point(b"G", G);
+    dleq_statement(&mut prover, var_x, var_A, var_G, var_B, var_H);
+    let proof = prover.prove_batchable().unwrap();
+
+    // An identity point is reported with its label
+    let mut transcript = Transcript::new(b"DLEQTest");
+    let mut verifier = Verifier::new(b"DLEQProof", &mut transcript);
+    verifier.allocate_scalar(b"x");
+    match verifier.allocate_point(b"B", CompressedRistretto::default()) {
+        Err(ProofError::IdentityPoint { label }) => assert_eq!(label, "B"),
+        _ => panic!("expected an identity point error"),
+    }
+
+    let verify = |proof: &zkp::BatchableProof| {
+        let mut transcript = Transcript::new(b"DLEQTest");
+        let mut verifier = Verifier::new(b"DLEQProof", &mut transcript);
+        let var_x = verifier.allocate_scalar(b"x");
+        let var_B = verifier.allocate_point(b"B", B.compress()).unwrap();
+        let var_H = verifier.allocate_point(b"H", H.compress()).unwrap();
+        let var_A = verifier.allocate_point(b"A", cmpr_A).unwrap();
+        let var_G = verifier.allocate_point(b"G", cmpr_G).unwrap();
+        dleq_statement(&mut verifier, var_x, var_A, var_G, var_B, var_H);
+        verifier.verify_batchable(proof)
+    };
+
+    let mut bad_proof = proof.clone();
+    bad_proof.responses.push(Scalar::one());
+    match verify(&bad_proof) {
+        Err(e @ ProofError::WrongResponseCount { expected: 1, found: 2 }) => {
+            // A malformed proof isn't a verification failure
+            assert!(!e.is_verification_failure());
+        }
+        _ => panic!("expected a response count error"),
+    }
+
+    let mut bad_proof = proof.clone();
+    bad_proof.commitments[1] = CompressedRistretto::default();
+    match verify(&bad_proof) {
+        Err(ProofError::InvalidCommitment { constraint: 1 }) => {}
+        _ => panic!("expected an invalid commitment error"),
+    }
+
+    let mut bad_proof = proof.clone();
+    bad_proof.responses[0] += Scalar::one();
+    let err = verify(&bad_proof).unwrap_err();
+    assert!(matches!(err, ProofError::VerificationFailure));
+    assert!(err.is_verification_failure());
+
+    // Errors for individual proofs in a batch carry the proof index
+    let mut bad_proof = proof.clone();
+    bad_proof.commitments.pop();
+    let mut transcripts = vec![Transcript::new(b"DLEQTest"); 2];
+    let mut verifier = BatchVerifier::new(b"DLEQProof", 2, transcripts.iter_mut().collect()).unwrap();
+    let var_x = verifier.allocate_scalar(b"x");
+    let var_B = verifier.allocate_static_point(b"B", B.compress()).unwrap();
+    let var_H = verifier.allocate_static_point(b"H", H.compress()).unwrap();
+    let var_A = verifier.allocate_instance_point(b"A", vec![cmpr_A; 2]).unwrap();
+    let var_G = verifier.allocate_instance_point(b"G", vec![cmpr_G; 2]).unwrap();
+    dleq_statement(&mut verifier, var_x, var_A, var_G, var_B, var_H);
+    let err = verifier.verify_batchable(&[proof, bad_proof]).unwrap_err();
+    assert!(err.is_verification_failure());
+    match err {
+        ProofError::BatchProofFailure { index: 1, source } => assert!(matches!(
+            *source,
+            ProofError::WrongCommitmentCount { expected: 2, found: 1 }
+        )),
+        _ => panic!("expected an error for the second proof"),
+    }
+}
+
+#[test]
+fn multi_batch_reports_invalid_commitments() {
+    use curve25519_dalek::ristretto::CompressedRistretto;
+    use zkp::toolbox::multi_batch_verifier::MultiBatchVerifier;
+    use zkp::toolbox::TranscriptProtocol;
+    use zkp::{BatchableProof, ProofError};
+
+    let B = dalek_constants::RISTRETTO_BASEPOINT_POINT;
+    let H = RistrettoPoint::hash_from_bytes::<Sha512>(B.compress().as_bytes());
+    let A = (B * Scalar::from(7u64)).compress();
+    let G = (H * Scalar::from(7u64)).compress();
+
+    // A cheating prover computes the challenge over a commitment
+    // which is not a valid point encoding
+    let commitments = vec![B.compress(), CompressedRistretto([0xff; 32])];
+    let mut transcript = Transcript::new(b"DLEQTest");
+    transcript.domain_sep(b"DLEQProof");
+    transcript.append_scalar_var(b"x");
+    for (label, point) in [(b"B", B.compress()), (b"H", H.compress()), (b"A", A), (b"G", G)].iter() {
+        transcript.append_compressed_point_var(*label, point);
+    }
+    transcript.validate_and_append_blinding_commitment(b"A", &commitments[0]).unwrap();
+    transcript.validate_and_append_blinding_commitment(b"G", &commitments[1]).unwrap();
+    let proof = BatchableProof {
+        challenges: vec![transcript.get_challenge(b"chal")],
+        commitments,
+        responses: vec![Scalar::one()],
+    };
+
+    let mut transcript = Transcript::new(b"DLEQTest");
+    let mut verifier = Verifier::new(b"DLEQProof", &mut transcript);
+    let var_x = verifier.allocate_scalar(b"x");
+    let var_B = verifier.allocate_point(b"B", B.compress()).unwrap();
+    let var_H = verifier.allocate_point(b"H", H.compress()).unwrap();
+    let var_A = verifier.allocate_point(b"A", A).unwrap();
+    let var_G = verifier.allocate_point(b"G", G).unwrap();
+    dleq_statement(&mut verifier, var_x, var_A, var_G, var_B, var_H);
+
+    let mut batch = MultiBatchVerifier::new();
+    assert!(matches!(
+        batch.add_proof(verifier, &proof),
+        Err(ProofError::InvalidCommitment { constraint: 1 })
+    ));
+    assert!(batch.is_empty());
+}
+
+#[test]
+fn labels_built_at_runtime_match_static_labels() {
+    use zkp::toolbox::prepared_verifier::PreparedVerifier;
+
+    let B = dalek_constants::RISTRETTO_BASEPOINT_POINT;
+    let H = RistrettoPoint::hash_from_bytes::<Sha512>(B.compress().as_bytes());
+
+    let x = Scalar::from(89327492234u64);
+    let A = B * x;
+    let G = H * x;
+
+    // Labels owned by the caller, e.g. read from a configuration
+    let proof_label = format!("DLEQ{}", "Proof");
+    let names: Vec<String> = ["x", "B", "H", "A", "G"].iter().map(|s| s.to_string()).collect();
+
+    let (proof, cmpr_A, cmpr_G) = {
+        let mut transcript = Transcript::new(b"DLEQTest");
+        let mut prover = Prover::new(proof_label.as_bytes(), &mut transcript);
+        let var_x = prover.allocate_scalar(names[0].as_bytes(), Some(x));
+        let (var_B, _) = prover.allocate_point(names[1].as_bytes(), B);
+        let (var_H, _) = prover.allocate_point(names[2].as_bytes(), H);
+        let (var_A, cmpr_A) = prover.allocate_point(names[3].as_bytes(), A);
+        let (var_G, cmpr_G) = prover.allocate_point(names[4].as_bytes(), G);
+        dleq_statement(&mut prover, var_x, var_A, var_G, var_B, var_H);
+        (prover.prove_batchable().unwrap(), cmpr_A, cmpr_G)
+    };
+
+    // The transcript encoding is unchanged, so static labels verify it
+    let mut transcript = Transcript::new(b"DLEQTest");
+    let mut verifier = Verifier::new(b"DLEQProof", &mut transcript);
+    let var_x = verifier.allocate_scalar(b"x");
+    let var_B = verifier.allocate_point(b"B", B.compress()).unwrap();
+    let var_H = verifier.allocate_point(b"H", H.compress()).unwrap();
+    let var_A = verifier.allocate_point(b"A", cmpr_A).unwrap();
+    let var_G = verifier.allocate_point(b"G", cmpr_G).unwrap();
+    dleq_statement(&mut verifier, var_x, var_A, var_G, var_B, var_H);
+    assert!(verifier.verify_batchable(&proof).is_ok());
+
+    // A prepared verifier keeps its own copy of the labels
+    let statement = {
+        let labels = names.clone();
+        let mut verifier = PreparedVerifier::new(proof_label.as_bytes());
+        let var_x = verifier.allocate_scalar(labels[0].as_bytes());
+        let var_B = verifier.allocate_static_point(labels[1].as_bytes(), B.compress()).unwrap();
+        let var_H = verifier.allocate_static_point(labels[2].as_bytes(), H.compress()).unwrap();
+        let var_A = verifier.allocate_instance_point(labels[3].as_bytes());
+        let var_G = verifier.allocate_instance_point(labels[4].as_bytes());
+        dleq_statement(&mut verifier, var_x, var_A, var_G, var_B, var_H);
+        verifier.prepare().unwrap()
+    };
+    let mut transcript = Transcript::new(b"DLEQTest");
+    assert!(statement
+        .verify_batchable(&proof, &mut transcript, &[cmpr_A, cmpr_G])
+        .is_ok());
+}
+
+#[test]
+fn prepared_statement_rejects_wrong_instance_size() {
+    use zkp::toolbox::prepared_verifier::PreparedVerifier;
+
+    let B = dalek_constants::RISTRETTO_BASEPOINT_POINT;
+    let H = RistrettoPoint::hash_from_bytes::<Sha512>(B.compress().as_bytes());
+
+    let x = Scalar::from(89327492234u64);
+    let A = B * x;
+    let G = H * x;
+
+    let (proof, cmpr_A, cmpr_G) = {
+        let mut transcript = Transcript::new(b"DLEQTest");
+        let mut prover = Prover::new(b"DLEQProof", &mut transcript);
+        let var_x = prover.allocate_scalar(b"x", Some(x));
+        let (var_B, _) = prover.allocate_point(b"B", B);
+        let (var_H, _) = prover.allocate_point(b"H", H);
+        let (var_A, cmpr_A) = prover.allocate_point(b"A", A);
+        let (var_G, cmpr_G) = prover.allocate_point(b"G", G);
+        dleq_statement(&mut prover, var_x, var_A, var_G, var_B, var_H);
+        (prover.prove_batchable().unwrap(), cmpr_A, cmpr_G)
+    };
+
+    let statement = {
+        let mut verifier = PreparedVerifier::new(b"DLEQProof");
+        let var_x = verifier.allocate_scalar(b"x");
+        let var_B = verifier.allocate_static_point(b"B", B.compress()).unwrap();
+        let var_H = verifier.allocate_static_point(b"H", H.compress()).unwrap();
+        let var_A = verifier.allocate_instance_point(b"A");
+        let var_G = verifier.allocate_instance_point(b"G");
+        dleq_statement(&mut verifier, var_x, var_A, var_G, var_B, var_H);
+        verifier.prepare().unwrap()
+    };
+    assert_eq!(statement.num_instance_points(), 2);
+
+    let mut transcript = Transcript::new(b"DLEQTest");
+    assert!(matches!(
+        statement.verify_batchable(&proof, &mut transcript, &[cmpr_A]),
+        Err(zkp::ProofError::WrongInstanceSize { expected: 2, found: 1 })
+    ));
+
+    // In a batch, the error is reported for the proof it belongs to
+    let proofs = vec![proof.clone(), proof];
+    let instances = vec![vec![cmpr_A, cmpr_G], vec![cmpr_A, cmpr_G, cmpr_G]];
+    let mut transcripts = vec![Transcript::new(b"DLEQTest"); 2];
+    match statement.batch_verify(&proofs, transcripts.iter_mut().collect(), &instances) {
+        Err(zkp::ProofError::BatchProofFailure { index: 1, source }) => assert!(matches!(
+            *source,
+            zkp::ProofError::WrongInstanceSize { expected: 2, found: 3 }
+        )),
+        result => panic!("unexpected result {:?}", result),
+    }
+}
+
+#[test]
+fn normalized_statements_drop_duplicate_constraints() {
+    fn repeated_statement<CS: SchnorrCS>(
+        cs: &mut CS,
+        x: CS::ScalarVar,
+        y: CS::ScalarVar,
+        A: CS::PointVar,
+        G: CS::PointVar,
+        B: CS::PointVar,
+        H: CS::PointVar,
+    ) {
+        cs.constrain(A, vec![(x, B), (y, H)]);
+        cs.constrain(G, vec![(x, H)]);
+        cs.constrain(A, vec![(y, H), (x, B)]);
+    }
+
+    let B = dalek_constants::RISTRETTO_BASEPOINT_POINT;
+    let H = RistrettoPoint::hash_from_bytes::<Sha512>(B.compress().as_bytes());
+    let x = Scalar::from(89327492234u64);
+    let y = Scalar::from(1337u64);
+    let (A, G) = (B * x + H * y, H * x);
+
+    let mut transcript = Transcript::new(b"NormalizeTest");
+    let mut prover = Prover::new(b"RepeatedProof", &mut transcript);
+    let var_x = prover.allocate_scalar(b"x", Some(x));
+    let var_y = prover.allocate_scalar(b"y", Some(y));
+    let (var_B, cmpr_B) = prover.allocate_point(b"B", B);
+    let (var_H, cmpr_H) = prover.allocate_point(b"H", H);
+    let (var_A, cmpr_A) = prover.allocate_point(b"A", A);
+    let (var_G, cmpr_G) = prover.allocate_point(b"G", G);
+    repeated_statement(&mut prover, var_x, var_y, var_A, var_G, var_B, var_H);
+    prover.normalize();
+    let proof = prover.prove_batchable().unwrap();
+    assert_eq!(proof.commitments.len(), 2);
+
+    for normalize in [true, false] {
+        let mut transcript = Transcript::new(b"NormalizeTest");
+        let mut verifier = Verifier::new(b"RepeatedProof", &mut transcript);
+        let var_x = verifier.allocate_scalar(b"x");
+        let var_y = verifier.allocate_scalar(b"y");
+        let var_B = verifier.allocate_point(b"B", cmpr_B).unwrap();
+        let var_H = verifier.allocate_point(b"H", cmpr_H).unwrap();
+        let var_A = verifier.allocate_point(b"A", cmpr_A).unwrap();
+        let var_G = verifier.allocate_point(b"G", cmpr_G).unwrap();
+        repeated_statement(&mut verifier, var_x, var_y, var_A, var_G, var_B, var_H);
+        if normalize {
+            verifier.normalize();
+            assert!(verifier.verify_batchable(&proof).is_ok());
+        } else {
+            // Both sides have to normalize the statement
+            assert!(matches!(
+                verifier.verify_batchable(&proof),
+                Err(zkp::ProofError::WrongCommitmentCount { expected: 3, found: 2 })
+            ));
+        }
+    }
+
+    let mut transcript = Transcript::new(b"NormalizeTest");
+    let mut verifier = BatchVerifier::new(b"RepeatedProof", 1, vec![&mut transcript]).unwrap();
+    let var_x = verifier.allocate_scalar(b"x");
+    let var_y = verifier.allocate_scalar(b"y");
+    let var_B = verifier.allocate_static_point(b"B", cmpr_B).unwrap();
+    let var_H = verifier.allocate_static_point(b"H", cmpr_H).unwrap();
+    let var_A = verifier.allocate_instance_point(b"A", vec![cmpr_A]).unwrap();
+    let var_G = verifier.allocate_instance_point(b"G", vec![cmpr_G]).unwrap();
+    repeated_statement(&mut verifier, var_x, var_y, var_A, var_G, var_B, var_H);
+    verifier.normalize();
+    assert!(verifier.verify_batchable(&[proof]).is_ok());
+}
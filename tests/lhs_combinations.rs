@@ -0,0 +1,189 @@
+#![allow(non_snake_case)]
+
+extern crate curve25519_dalek;
+extern crate rand;
+#[macro_use]
+extern crate zkp;
+
+use rand::thread_rng;
+
+use curve25519_dalek::constants as dalek_constants;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use zkp::toolbox::{batch_verifier::BatchVerifier, prover::Prover, verifier::Verifier, SchnorrCS};
+use zkp::{ProofError, Transcript};
+
+define_proof! {differences, "differences", (x, r), (A, B, C, D), (G, H) : (A - B) = (G ^ x) && (C * D ^ -1) = (H ^ r)}
+define_proof! {weighted, "weighted", (x), (A, B), (G) : (A ^ 2 * B ^ -3) = (G ^ x)}
+
+#[test]
+fn constrain_combination_with_toolbox() {
+    let G = dalek_constants::RISTRETTO_BASEPOINT_POINT;
+    let x = Scalar::random(&mut thread_rng());
+    let B = RistrettoPoint::random(&mut thread_rng());
+    let A = B + G * x;
+
+    let mut transcript = Transcript::new(b"LhsCombinationTest");
+    let mut prover = Prover::new(b"LhsCombination", &mut transcript);
+    let var_x = prover.allocate_scalar(b"x", Some(x));
+    let (var_A, cmpr_A) = prover.allocate_point(b"A", A);
+    let (var_B, cmpr_B) = prover.allocate_point(b"B", B);
+    let (var_G, cmpr_G) = prover.allocate_point(b"G", G);
+    prover.constrain_combination(vec![(Scalar::one(), var_A), (-Scalar::one(), var_B)], vec![(var_x, var_G)]);
+    let proof = prover.prove_batchable().unwrap();
+
+    let mut transcript = Transcript::new(b"LhsCombinationTest");
+    let mut verifier = Verifier::new(b"LhsCombination", &mut transcript);
+    let var_x = verifier.allocate_scalar(b"x");
+    let var_A = verifier.allocate_point(b"A", cmpr_A).unwrap();
+    let var_B = verifier.allocate_point(b"B", cmpr_B).unwrap();
+    let var_G = verifier.allocate_point(b"G", cmpr_G).unwrap();
+    verifier.constrain_combination(vec![(Scalar::one(), var_A), (-Scalar::one(), var_B)], vec![(var_x, var_G)]);
+    let compact = verifier.compact_from_batchable(&proof).unwrap();
+
+    let mut transcript = Transcript::new(b"LhsCombinationTest");
+    let mut verifier = Verifier::new(b"LhsCombination", &mut transcript);
+    let var_x = verifier.allocate_scalar(b"x");
+    let var_A = verifier.allocate_point(b"A", cmpr_A).unwrap();
+    let var_B = verifier.allocate_point(b"B", cmpr_B).unwrap();
+    let var_G = verifier.allocate_point(b"G", cmpr_G).unwrap();
+    verifier.constrain_combination(vec![(Scalar::one(), var_A), (-Scalar::one(), var_B)], vec![(var_x, var_G)]);
+    assert!(verifier.verify_compact(&compact).is_ok());
+
+    // The same statement with the signs swapped doesn't hold
+    let mut transcript = Transcript::new(b"LhsCombinationTest");
+    let mut batch_verifier = BatchVerifier::new(b"LhsCombination", 1, vec![&mut transcript]).unwrap();
+    let var_x = batch_verifier.allocate_scalar(b"x");
+    let var_A = batch_verifier.allocate_instance_point(b"A", vec![cmpr_A]).unwrap();
+    let var_B = batch_verifier.allocate_instance_point(b"B", vec![cmpr_B]).unwrap();
+    let var_G = batch_verifier.allocate_static_point(b"G", cmpr_G).unwrap();
+    batch_verifier.constrain_combination(vec![(-Scalar::one(), var_A), (Scalar::one(), var_B)], vec![(var_x, var_G)]);
+    assert!(matches!(
+        batch_verifier.verify_batchable(std::slice::from_ref(&proof)),
+        Err(ProofError::VerificationFailure)
+    ));
+
+    let mut transcript = Transcript::new(b"LhsCombinationTest");
+    let mut batch_verifier = BatchVerifier::new(b"LhsCombination", 1, vec![&mut transcript]).unwrap();
+    let var_x = batch_verifier.allocate_scalar(b"x");
+    let var_A = batch_verifier.allocate_instance_point(b"A", vec![cmpr_A]).unwrap();
+    let var_B = batch_verifier.allocate_instance_point(b"B", vec![cmpr_B]).unwrap();
+    let var_G = batch_verifier.allocate_static_point(b"G", cmpr_G).unwrap();
+    batch_verifier.constrain_combination(vec![(Scalar::one(), var_A), (-Scalar::one(), var_B)], vec![(var_x, var_G)]);
+    assert!(batch_verifier.verify_batchable(&[proof]).is_ok());
+}
+
+#[test]
+fn define_proof_with_lhs_combinations() {
+    let G = dalek_constants::RISTRETTO_BASEPOINT_POINT;
+    let H = RistrettoPoint::random(&mut thread_rng());
+
+    let mut proofs = Vec::new();
+    let mut points = Vec::new();
+    for _ in 0..3 {
+        let x = Scalar::random(&mut thread_rng());
+        let r = Scalar::random(&mut thread_rng());
+        let B = RistrettoPoint::random(&mut thread_rng());
+        let D = RistrettoPoint::random(&mut thread_rng());
+        let A = B + G * x;
+        let C = D + H * r;
+
+        let mut transcript = Transcript::new(b"Differences");
+        let (proof, cmpr) = differences::prove_batchable(
+            &mut transcript,
+            differences::ProveAssignments {
+                witness: &differences::Witness::Clause1 { x, r },
+                A: &A,
+                B: &B,
+                C: &C,
+                D: &D,
+                G: &G,
+                H: &H,
+            },
+        )
+        .unwrap();
+
+        let mut transcript = Transcript::new(b"Differences");
+        assert!(differences::verify_batchable(
+            &proof,
+            &mut transcript,
+            differences::VerifyAssignments {
+                A: &cmpr.A,
+                B: &cmpr.B,
+                C: &cmpr.C,
+                D: &cmpr.D,
+                G: &cmpr.G,
+                H: &cmpr.H,
+            },
+        )
+        .is_ok());
+
+        // Swapping the points of a difference negates it
+        let mut transcript = Transcript::new(b"Differences");
+        assert!(differences::verify_batchable(
+            &proof,
+            &mut transcript,
+            differences::VerifyAssignments {
+                A: &cmpr.B,
+                B: &cmpr.A,
+                C: &cmpr.C,
+                D: &cmpr.D,
+                G: &cmpr.G,
+                H: &cmpr.H,
+            },
+        )
+        .is_err());
+
+        proofs.push(proof);
+        points.push(cmpr);
+    }
+
+    let mut transcripts = vec![Transcript::new(b"Differences"); 3];
+    assert!(differences::batch_verify(
+        &proofs,
+        transcripts.iter_mut().collect(),
+        differences::BatchVerifyAssignments {
+            A: points.iter().map(|cmpr| cmpr.A).collect(),
+            B: points.iter().map(|cmpr| cmpr.B).collect(),
+            C: points.iter().map(|cmpr| cmpr.C).collect(),
+            D: points.iter().map(|cmpr| cmpr.D).collect(),
+            G: points[0].G,
+            H: points[0].H,
+        },
+    )
+    .is_ok());
+}
+
+#[test]
+fn define_proof_with_integer_powers() {
+    let G = dalek_constants::RISTRETTO_BASEPOINT_POINT;
+    let x = Scalar::random(&mut thread_rng());
+    let B = RistrettoPoint::random(&mut thread_rng());
+    // A ^ 2 * B ^ -3 = G ^ x
+    let A = (B * Scalar::from(3u64) + G * x) * Scalar::from(2u64).invert();
+
+    let mut transcript = Transcript::new(b"Weighted");
+    let (proof, cmpr) = weighted::prove_compact(
+        &mut transcript,
+        weighted::ProveAssignments {
+            witness: &weighted::Witness::Clause1 { x },
+            A: &A,
+            B: &B,
+            G: &G,
+        },
+    )
+    .unwrap();
+
+    let mut transcript = Transcript::new(b"Weighted");
+    assert!(weighted::verify_compact(
+        &proof,
+        &mut transcript,
+        weighted::VerifyAssignments {
+            A: &cmpr.A,
+            B: &cmpr.B,
+            G: &cmpr.G,
+        },
+    )
+    .is_ok());
+}
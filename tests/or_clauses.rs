@@ -1,3 +1,5 @@
+#![allow(non_snake_case)]
+
 extern crate rand;
 
 use rand::{thread_rng, CryptoRng, RngCore};
@@ -16,22 +18,19 @@ use zkp::ProofError;
 
 
 define_proof! {or_clause_test, "or_clause_test", (x,y), (A, B, G), () : A = (G ^ x) || B = (G ^ y)}
+define_proof! {three_way_or, "three_way_or", (x, y, z), (A, B, C), (G) : A = (G ^ x) || B = (G ^ y) || C = (G ^ z)}
+define_proof! {shared_secret, "shared_secret", (x, y), (A, B), (G, H) : A = (G ^ x) || B = (H ^ x * G ^ y)}
+define_proof! {repeated_secrets, "repeated_secrets", (x, y, z), (A, B, C), (G, H) : A = (G ^ x * H ^ y) && B = (H ^ x) || C = (G ^ z * H ^ x)}
 
 /// Defines how the construction interacts with the transcript.
 trait TranscriptProtocol {
     fn append_message_example(&mut self, message: &[u8]);
-    fn hash_to_group(self) -> RistrettoPoint;
 }
 
 impl TranscriptProtocol for Transcript {
     fn append_message_example(&mut self, message: &[u8]) {
         self.append_message(b"msg", message);
     }
-    fn hash_to_group(mut self) -> RistrettoPoint {
-        let mut bytes = [0u8; 64];
-        self.challenge_bytes(b"output", &mut bytes);
-        RistrettoPoint::from_uniform_bytes(&bytes)
-    }
 }
 
 #[derive(Clone)]
@@ -77,11 +76,14 @@ impl KeyPair {
 
     fn sign(&self, clause: usize, message: &[u8], sig_transcript: &mut Transcript) -> Signature {
         sig_transcript.append_message_example(message);
+        let witness = match clause {
+            1 => or_clause_test::Witness::Clause1 { x: self.sk1.0 },
+            _ => or_clause_test::Witness::Clause2 { y: self.sk2.0 },
+        };
         let result = or_clause_test::prove_compact(
             sig_transcript,
             or_clause_test::ProveAssignments {
-                x: &match clause == 1 { true => Some(self.sk1.0), false => None },
-                y: &match clause == 2 { true => Some(self.sk2.0), false => None },
+                witness: &witness,
                 A: &self.pk1.0,
                 B: &self.pk2.0,
                 G: &dalek_constants::RISTRETTO_BASEPOINT_POINT,
@@ -92,7 +94,7 @@ impl KeyPair {
         }
 
         let (proof, _points) = result.unwrap();
-        return Signature(proof);
+        Signature(proof)
     }
 }
 
@@ -192,4 +194,324 @@ fn create_and_verify_or_sig() {
     assert!(sig4
         .verify(msg2, &pk2.0, &pk2.1, &mut Transcript::new(b"Wrong"))
         .is_err());
-}
\ No newline at end of file
+}
+#[test]
+fn witness_variants_hold_the_secrets_of_their_clause() {
+    // Each secret appears once per variant, in declaration order
+    let x = Scalar::random(&mut thread_rng());
+    let y = Scalar::random(&mut thread_rng());
+    let z = Scalar::random(&mut thread_rng());
+    let witness = repeated_secrets::Witness::Clause1 { x, y };
+    match &witness {
+        repeated_secrets::Witness::Clause1 { x: x1, y: y1 } => assert_eq!((*x1, *y1), (x, y)),
+        repeated_secrets::Witness::Clause2 { .. } => unreachable!(),
+    }
+    let _ = repeated_secrets::Witness::Clause2 { x, z };
+
+    let G = dalek_constants::RISTRETTO_BASEPOINT_POINT;
+    let (A, B, C) = (G * x, G * y, G * z);
+    let witnesses = [
+        three_way_or::Witness::Clause1 { x },
+        three_way_or::Witness::Clause2 { y },
+        three_way_or::Witness::Clause3 { z },
+    ];
+    for witness in witnesses.iter() {
+        let mut transcript = Transcript::new(b"ThreeWayOr");
+        let (proof, points) = three_way_or::prove_compact(
+            &mut transcript,
+            three_way_or::ProveAssignments { witness, A: &A, B: &B, C: &C, G: &G },
+        )
+        .unwrap();
+
+        let mut transcript = Transcript::new(b"ThreeWayOr");
+        assert!(three_way_or::verify_compact(
+            &proof,
+            &mut transcript,
+            three_way_or::VerifyAssignments {
+                A: &points.A,
+                B: &points.B,
+                C: &points.C,
+                G: &points.G,
+            },
+        )
+        .is_ok());
+    }
+}
+
+#[test]
+fn secrets_shared_between_clauses() {
+    let G = dalek_constants::RISTRETTO_BASEPOINT_POINT;
+    let H = RistrettoPoint::random(&mut thread_rng());
+    let x = Scalar::random(&mut thread_rng());
+    let y = Scalar::random(&mut thread_rng());
+
+    // In the first instance, A = G^x is true and B is random; in
+    // the second, B = H^x * G^y is true and A is random
+    let A = [G * x, RistrettoPoint::random(&mut thread_rng())];
+    let B = [RistrettoPoint::random(&mut thread_rng()), H * x + G * y];
+    let witnesses = [
+        shared_secret::Witness::Clause1 { x },
+        shared_secret::Witness::Clause2 { x, y },
+    ];
+
+    let mut proofs = Vec::new();
+    let mut points = Vec::new();
+    for (j, witness) in witnesses.iter().enumerate() {
+        let mut transcript = Transcript::new(b"SharedSecret");
+        let (proof, cmpr) = shared_secret::prove_batchable(
+            &mut transcript,
+            shared_secret::ProveAssignments { witness, A: &A[j], B: &B[j], G: &G, H: &H },
+        )
+        .unwrap();
+        // One challenge per clause, and one response per secret of each clause
+        assert_eq!(proof.challenges.len(), 2);
+        assert_eq!(proof.responses.len(), 3);
+
+        let mut transcript = Transcript::new(b"SharedSecret");
+        let compact = shared_secret::batchable_to_compact(
+            &proof,
+            &mut transcript,
+            shared_secret::VerifyAssignments { A: &cmpr.A, B: &cmpr.B, G: &cmpr.G, H: &cmpr.H },
+        )
+        .unwrap();
+        let mut transcript = Transcript::new(b"SharedSecret");
+        assert!(shared_secret::verify_compact(
+            &compact,
+            &mut transcript,
+            shared_secret::VerifyAssignments { A: &cmpr.A, B: &cmpr.B, G: &cmpr.G, H: &cmpr.H },
+        )
+        .is_ok());
+
+        proofs.push(proof);
+        points.push(cmpr);
+    }
+
+    let mut transcripts = vec![Transcript::new(b"SharedSecret"); 2];
+    assert!(shared_secret::batch_verify(
+        &proofs,
+        transcripts.iter_mut().collect(),
+        shared_secret::BatchVerifyAssignments {
+            A: points.iter().map(|cmpr| cmpr.A).collect(),
+            B: points.iter().map(|cmpr| cmpr.B).collect(),
+            G: points[0].G,
+            H: points[0].H,
+        },
+    )
+    .is_ok());
+
+    // Swapping the responses of the secret shared by both clauses
+    // breaks the proof
+    let mut bad_proof = proofs[0].clone();
+    bad_proof.responses.swap(0, 1);
+    let mut transcript = Transcript::new(b"SharedSecret");
+    assert!(shared_secret::verify_batchable(
+        &bad_proof,
+        &mut transcript,
+        shared_secret::VerifyAssignments {
+            A: &points[0].A,
+            B: &points[0].B,
+            G: &points[0].G,
+            H: &points[0].H,
+        },
+    )
+    .is_err());
+}
+
+#[test]
+fn clauses_with_several_constraints() {
+    let G = dalek_constants::RISTRETTO_BASEPOINT_POINT;
+    let H = RistrettoPoint::random(&mut thread_rng());
+    let x = Scalar::random(&mut thread_rng());
+    let y = Scalar::random(&mut thread_rng());
+    let z = Scalar::random(&mut thread_rng());
+    let (A, B, C) = (G * x + H * y, H * x, G * z + H * x);
+
+    let witnesses = [
+        repeated_secrets::Witness::Clause1 { x, y },
+        repeated_secrets::Witness::Clause2 { x, z },
+    ];
+    for witness in witnesses.iter() {
+        let mut transcript = Transcript::new(b"RepeatedSecrets");
+        let (proof, points) = repeated_secrets::prove_compact(
+            &mut transcript,
+            repeated_secrets::ProveAssignments { witness, A: &A, B: &B, C: &C, G: &G, H: &H },
+        )
+        .unwrap();
+
+        let mut transcript = Transcript::new(b"RepeatedSecrets");
+        assert!(repeated_secrets::verify_compact(
+            &proof,
+            &mut transcript,
+            repeated_secrets::VerifyAssignments {
+                A: &points.A,
+                B: &points.B,
+                C: &points.C,
+                G: &points.G,
+                H: &points.H,
+            },
+        )
+        .is_ok());
+    }
+}
+
+mod constraint_api {
+    use super::*;
+
+    use zkp::toolbox::{prover::Prover, verifier::Verifier, SchnorrCS};
+
+    fn either_dlog_statement<CS: SchnorrCS>(
+        cs: &mut CS,
+        x: CS::ScalarVar,
+        y: CS::ScalarVar,
+        A: CS::PointVar,
+        B: CS::PointVar,
+        G: CS::PointVar,
+        H: CS::PointVar,
+    ) {
+        cs.or(|cs| {
+            cs.constrain(A, vec![(x, G)]);
+            cs.and(|cs| cs.constrain(B, vec![(y, H)]));
+        });
+    }
+
+    #[test]
+    fn create_and_verify_or_statement() {
+        let G = dalek_constants::RISTRETTO_BASEPOINT_POINT;
+        let H = RistrettoPoint::random(&mut thread_rng());
+        let x = Scalar::random(&mut thread_rng());
+        let y = Scalar::random(&mut thread_rng());
+        let (A, B) = (G * x, H * y);
+
+        for (x_known, y_known) in [(Some(x), None), (None, Some(y))] {
+            let mut transcript = Transcript::new(b"OrTest");
+            let mut prover = Prover::new(b"EitherDlog", &mut transcript);
+            let var_x = prover.allocate_scalar(b"x", x_known);
+            let var_y = prover.allocate_scalar(b"y", y_known);
+            let (var_A, cmpr_A) = prover.allocate_point(b"A", A);
+            let (var_B, cmpr_B) = prover.allocate_point(b"B", B);
+            let (var_G, cmpr_G) = prover.allocate_point(b"G", G);
+            let (var_H, cmpr_H) = prover.allocate_point(b"H", H);
+            either_dlog_statement(&mut prover, var_x, var_y, var_A, var_B, var_G, var_H);
+            let proof = prover.prove_compact().unwrap();
+
+            let mut transcript = Transcript::new(b"OrTest");
+            let mut verifier = Verifier::new(b"EitherDlog", &mut transcript);
+            let var_x = verifier.allocate_scalar(b"x");
+            let var_y = verifier.allocate_scalar(b"y");
+            let var_A = verifier.allocate_point(b"A", cmpr_A).unwrap();
+            let var_B = verifier.allocate_point(b"B", cmpr_B).unwrap();
+            let var_G = verifier.allocate_point(b"G", cmpr_G).unwrap();
+            let var_H = verifier.allocate_point(b"H", cmpr_H).unwrap();
+            either_dlog_statement(&mut verifier, var_x, var_y, var_A, var_B, var_G, var_H);
+            assert!(verifier.verify_compact(&proof).is_ok());
+        }
+
+        // With the secrets of both alternatives, the first one is
+        // proven, and without either, there is nothing to prove
+        for (x_known, y_known) in [(Some(x), Some(y)), (None, None)] {
+            let mut transcript = Transcript::new(b"OrTest");
+            let mut prover = Prover::new(b"EitherDlog", &mut transcript);
+            let var_x = prover.allocate_scalar(b"x", x_known);
+            let var_y = prover.allocate_scalar(b"y", y_known);
+            let (var_A, cmpr_A) = prover.allocate_point(b"A", A);
+            let (var_B, cmpr_B) = prover.allocate_point(b"B", B);
+            let (var_G, cmpr_G) = prover.allocate_point(b"G", G);
+            let (var_H, cmpr_H) = prover.allocate_point(b"H", H);
+            either_dlog_statement(&mut prover, var_x, var_y, var_A, var_B, var_G, var_H);
+            let proof = match prover.prove_compact() {
+                Ok(proof) => proof,
+                Err(e) => {
+                    assert!(x_known.is_none() && matches!(e, ProofError::InputMismatch));
+                    continue;
+                }
+            };
+
+            let mut transcript = Transcript::new(b"OrTest");
+            let mut verifier = Verifier::new(b"EitherDlog", &mut transcript);
+            let var_x = verifier.allocate_scalar(b"x");
+            let var_y = verifier.allocate_scalar(b"y");
+            let var_A = verifier.allocate_point(b"A", cmpr_A).unwrap();
+            let var_B = verifier.allocate_point(b"B", cmpr_B).unwrap();
+            let var_G = verifier.allocate_point(b"G", cmpr_G).unwrap();
+            let var_H = verifier.allocate_point(b"H", cmpr_H).unwrap();
+            either_dlog_statement(&mut verifier, var_x, var_y, var_A, var_B, var_G, var_H);
+            assert!(verifier.verify_compact(&proof).is_ok());
+        }
+    }
+
+    #[test]
+    fn real_clause_is_chosen_without_checking_the_constraints() {
+        let G = dalek_constants::RISTRETTO_BASEPOINT_POINT;
+        let H = RistrettoPoint::random(&mut thread_rng());
+        let x = Scalar::random(&mut thread_rng());
+        let y = Scalar::random(&mut thread_rng());
+        let (A, B) = (G * x, H * y);
+
+        // The assignment to x is wrong, so only the second clause holds
+        for (real_clause, verifies) in [(None, false), (Some(1), false), (Some(2), true)] {
+            let mut transcript = Transcript::new(b"OrTest");
+            let mut prover = Prover::new(b"EitherDlog", &mut transcript);
+            let var_x = prover.allocate_scalar(b"x", Some(x + Scalar::one()));
+            let var_y = prover.allocate_scalar(b"y", Some(y));
+            let (var_A, cmpr_A) = prover.allocate_point(b"A", A);
+            let (var_B, cmpr_B) = prover.allocate_point(b"B", B);
+            let (var_G, cmpr_G) = prover.allocate_point(b"G", G);
+            let (var_H, cmpr_H) = prover.allocate_point(b"H", H);
+            either_dlog_statement(&mut prover, var_x, var_y, var_A, var_B, var_G, var_H);
+            if let Some(clause_nr) = real_clause {
+                prover.set_real_clause(clause_nr);
+            }
+            let proof = prover.prove_compact().unwrap();
+
+            let mut transcript = Transcript::new(b"OrTest");
+            let mut verifier = Verifier::new(b"EitherDlog", &mut transcript);
+            let var_x = verifier.allocate_scalar(b"x");
+            let var_y = verifier.allocate_scalar(b"y");
+            let var_A = verifier.allocate_point(b"A", cmpr_A).unwrap();
+            let var_B = verifier.allocate_point(b"B", cmpr_B).unwrap();
+            let var_G = verifier.allocate_point(b"G", cmpr_G).unwrap();
+            let var_H = verifier.allocate_point(b"H", cmpr_H).unwrap();
+            either_dlog_statement(&mut verifier, var_x, var_y, var_A, var_B, var_G, var_H);
+            assert_eq!(verifier.verify_compact(&proof).is_ok(), verifies);
+        }
+
+        // A clause which doesn't exist can't be proven
+        let mut transcript = Transcript::new(b"OrTest");
+        let mut prover = Prover::new(b"EitherDlog", &mut transcript);
+        let var_x = prover.allocate_scalar(b"x", Some(x));
+        let var_y = prover.allocate_scalar(b"y", Some(y));
+        let (var_A, _) = prover.allocate_point(b"A", A);
+        let (var_B, _) = prover.allocate_point(b"B", B);
+        let (var_G, _) = prover.allocate_point(b"G", G);
+        let (var_H, _) = prover.allocate_point(b"H", H);
+        either_dlog_statement(&mut prover, var_x, var_y, var_A, var_B, var_G, var_H);
+        prover.set_real_clause(3);
+        assert!(matches!(prover.prove_compact(), Err(ProofError::InputMismatch)));
+    }
+
+    #[test]
+    #[should_panic(expected = "disjunctions can't be nested")]
+    fn nested_disjunctions_are_rejected() {
+        let G = dalek_constants::RISTRETTO_BASEPOINT_POINT;
+        let mut transcript = Transcript::new(b"OrTest");
+        let mut prover = Prover::new(b"Nested", &mut transcript);
+        let var_x = prover.allocate_scalar(b"x", None);
+        let (var_G, _) = prover.allocate_point(b"G", G);
+        prover.or(|cs| {
+            cs.constrain(var_G, vec![(var_x, var_G)]);
+            cs.and(|cs| cs.or(|cs| cs.constrain(var_G, vec![(var_x, var_G)])));
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "a statement can only have one disjunction")]
+    fn second_disjunction_is_rejected() {
+        let G = dalek_constants::RISTRETTO_BASEPOINT_POINT;
+        let mut transcript = Transcript::new(b"OrTest");
+        let mut prover = Prover::new(b"TwoDisjunctions", &mut transcript);
+        let var_x = prover.allocate_scalar(b"x", None);
+        let (var_G, _) = prover.allocate_point(b"G", G);
+        prover.or(|cs| cs.constrain(var_G, vec![(var_x, var_G)]));
+        prover.or(|cs| cs.constrain(var_G, vec![(var_x, var_G)]));
+    }
+}
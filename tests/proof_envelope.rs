@@ -0,0 +1,267 @@
+#![allow(non_snake_case)]
+
+extern crate curve25519_dalek;
+extern crate rand;
+extern crate sha2;
+#[macro_use]
+extern crate zkp;
+
+use rand::thread_rng;
+use sha2::Sha512;
+
+use curve25519_dalek::constants as dalek_constants;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+
+use zkp::toolbox::registry::StatementRegistry;
+use zkp::toolbox::{prover::Prover, verifier::Verifier, SchnorrCS, Statement};
+use zkp::{EnvelopedProof, ProofEnvelope, ProofError, Transcript};
+
+define_proof! {dleq, "DLEQ Envelope Proof", (x), (A, B, H), (G) : A = (G ^ x) && B = (H ^ x) }
+define_proof! {dlog, "DLog Envelope Proof", (x), (A), (G) : A = (G ^ x) }
+
+fn dleq_envelopes() -> (ProofEnvelope, ProofEnvelope) {
+    let G = dalek_constants::RISTRETTO_BASEPOINT_POINT;
+    let H = RistrettoPoint::hash_from_bytes::<Sha512>(b"A VRF input, for instance");
+    let x = Scalar::random(&mut thread_rng());
+    let assignments = dleq::ProveAssignments {
+        witness: &dleq::Witness::Clause1 { x },
+        A: &(G * x),
+        B: &(H * x),
+        G: &G,
+        H: &H,
+    };
+
+    let (compact, points) = dleq::prove_compact(&mut Transcript::new(b"EnvelopeTest"), assignments).unwrap();
+    let (batchable, _) = dleq::prove_batchable(&mut Transcript::new(b"EnvelopeTest"), assignments).unwrap();
+
+    (
+        dleq::envelope(compact, &points.instance()),
+        dleq::envelope(batchable, &points.instance()),
+    )
+}
+
+fn dlog_envelope() -> ProofEnvelope {
+    let G = dalek_constants::RISTRETTO_BASEPOINT_POINT;
+    let x = Scalar::random(&mut thread_rng());
+    let (proof, points) = dlog::prove_batchable(
+        &mut Transcript::new(b"EnvelopeTest"),
+        dlog::ProveAssignments {
+            witness: &dlog::Witness::Clause1 { x },
+            A: &(G * x),
+            G: &G,
+        },
+    )
+    .unwrap();
+
+    dlog::envelope(proof, &points.instance())
+}
+
+#[test]
+fn envelope_encoding_is_canonical() {
+    let (compact, batchable) = dleq_envelopes();
+
+    for envelope in [compact, batchable].iter() {
+        let bytes = envelope.to_bytes();
+        assert_eq!(bytes[0], ProofEnvelope::VERSION);
+        let parsed = ProofEnvelope::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed.label, "DLEQ Envelope Proof");
+        assert_eq!(parsed.instance, envelope.instance);
+        assert_eq!(parsed.to_bytes(), bytes);
+
+        // Trailing or missing bytes are rejected
+        let mut longer = bytes.clone();
+        longer.push(0);
+        assert!(matches!(ProofEnvelope::from_bytes(&longer), Err(ProofError::MalformedEnvelope)));
+        assert!(matches!(
+            ProofEnvelope::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(ProofError::MalformedEnvelope)
+        ));
+
+        // So are unknown versions and proof kinds
+        let mut other_version = bytes.clone();
+        other_version[0] = 2;
+        assert!(matches!(
+            ProofEnvelope::from_bytes(&other_version),
+            Err(ProofError::UnsupportedVersion { version: 2 })
+        ));
+        let mut other_kind = bytes.clone();
+        other_kind[1] = 2;
+        assert!(matches!(ProofEnvelope::from_bytes(&other_kind), Err(ProofError::MalformedEnvelope)));
+
+        // And non-canonical scalars, here in the last response
+        let mut non_canonical = bytes.clone();
+        let len = non_canonical.len();
+        non_canonical[len - 32..].copy_from_slice(&[0xff; 32]);
+        assert!(matches!(ProofEnvelope::from_bytes(&non_canonical), Err(ProofError::MalformedEnvelope)));
+    }
+}
+
+/// Verify an encoded envelope with the statement `S`.
+fn verify_bytes<S: Statement>(bytes: &[u8], common: &S::CommonPoints) -> Result<(), ProofError> {
+    let envelope = ProofEnvelope::from_bytes(bytes)?;
+    S::verify_envelope(&envelope, &mut Transcript::new(b"EnvelopeTest"), common)
+}
+
+#[test]
+fn envelopes_verify_with_their_statement() {
+    let G = dalek_constants::RISTRETTO_BASEPOINT_COMPRESSED;
+    let dleq_common = dleq::CommonPoints { G };
+    let dlog_common = dlog::CommonPoints { G };
+
+    let (compact, batchable) = dleq_envelopes();
+    for envelope in [compact, batchable].iter() {
+        let bytes = envelope.to_bytes();
+        assert!(verify_bytes::<dleq::Statement>(&bytes, &dleq_common).is_ok());
+        assert!(dleq::verify_envelope(envelope, &mut Transcript::new(b"Wrong"), &dleq_common).is_err());
+
+        // Another statement rejects the envelope by its label
+        assert!(matches!(
+            verify_bytes::<dlog::Statement>(&bytes, &dlog_common),
+            Err(ProofError::UnknownStatement { .. })
+        ));
+    }
+    assert!(verify_bytes::<dlog::Statement>(&dlog_envelope().to_bytes(), &dlog_common).is_ok());
+
+    // An envelope relabeled for another statement fails
+    let mut relabeled = dlog_envelope();
+    relabeled.label = String::from("DLEQ Envelope Proof");
+    assert!(matches!(
+        verify_bytes::<dleq::Statement>(&relabeled.to_bytes(), &dleq_common),
+        Err(ProofError::WrongInstanceSize { expected: 3, found: 1 })
+    ));
+
+    // The verifier supplies the common points
+    let H = RistrettoPoint::hash_from_bytes::<Sha512>(b"Another generator").compress();
+    let envelope = dlog_envelope();
+    let other_common = dlog::CommonPoints { G: H };
+    assert!(dlog::verify_envelope(&envelope, &mut Transcript::new(b"EnvelopeTest"), &other_common).is_err());
+    assert!(matches!(envelope.proof, EnvelopedProof::Batchable(_)));
+}
+
+#[test]
+fn registry_routes_envelopes_by_label() {
+    let G = dalek_constants::RISTRETTO_BASEPOINT_COMPRESSED;
+    let mut registry = StatementRegistry::new();
+    registry.register::<dleq::Statement>(dleq::CommonPoints { G });
+    registry.register::<dlog::Statement>(dlog::CommonPoints { G });
+
+    let (compact, batchable) = dleq_envelopes();
+    for envelope in [compact, batchable, dlog_envelope()].iter() {
+        let bytes = envelope.to_bytes();
+        assert!(registry
+            .verify_envelope_bytes(&bytes, &mut Transcript::new(b"EnvelopeTest"))
+            .is_ok());
+        assert!(registry
+            .verify_envelope_bytes(&bytes, &mut Transcript::new(b"Wrong"))
+            .is_err());
+    }
+
+    // An envelope relabeled for another statement fails
+    let mut relabeled = dlog_envelope();
+    relabeled.label = String::from("DLEQ Envelope Proof");
+    assert!(matches!(
+        registry.verify_envelope(&relabeled, &mut Transcript::new(b"EnvelopeTest")),
+        Err(ProofError::WrongInstanceSize { expected: 3, found: 1 })
+    ));
+
+    // Unregistered statements are rejected
+    relabeled.label = String::from("Unknown Proof");
+    assert!(matches!(
+        registry.verify_envelope(&relabeled, &mut Transcript::new(b"EnvelopeTest")),
+        Err(ProofError::UnknownStatement { .. })
+    ));
+}
+
+#[test]
+fn registry_uses_the_registered_common_points() {
+    let H = RistrettoPoint::hash_from_bytes::<Sha512>(b"Another generator").compress();
+    let mut registry = StatementRegistry::new();
+    registry.register::<dlog::Statement>(dlog::CommonPoints { G: H });
+
+    let envelope = dlog_envelope();
+    assert!(registry
+        .verify_envelope(&envelope, &mut Transcript::new(b"EnvelopeTest"))
+        .is_err());
+
+    // The module's own entry point checks the label too
+    let common = dleq::CommonPoints {
+        G: dalek_constants::RISTRETTO_BASEPOINT_COMPRESSED,
+    };
+    assert!(matches!(
+        dleq::verify_envelope(&envelope, &mut Transcript::new(b"EnvelopeTest"), &common),
+        Err(ProofError::UnknownStatement { .. })
+    ));
+    assert!(matches!(envelope.proof, EnvelopedProof::Batchable(_)));
+}
+
+/// Verify a proof of knowledge of the discrete logs of all the
+/// instance points, whose number is only known at runtime.
+fn verify_dlogs(envelope: &ProofEnvelope, transcript: &mut Transcript) -> Result<(), ProofError> {
+    let mut verifier = Verifier::new(b"Runtime DLogs", transcript);
+    let var_G = verifier.allocate_point(b"G", dalek_constants::RISTRETTO_BASEPOINT_COMPRESSED)?;
+    for point in &envelope.instance {
+        let var_x = verifier.allocate_scalar(b"x");
+        let var_A = verifier.allocate_point(b"A", *point)?;
+        verifier.constrain(var_A, vec![(var_x, var_G)]);
+    }
+    match &envelope.proof {
+        EnvelopedProof::Compact(proof) => verifier.verify_compact(proof),
+        EnvelopedProof::Batchable(proof) => verifier.verify_batchable(proof),
+    }
+}
+
+fn dlogs_envelope(count: usize) -> ProofEnvelope {
+    let G = dalek_constants::RISTRETTO_BASEPOINT_POINT;
+    let mut transcript = Transcript::new(b"EnvelopeTest");
+    let mut prover = Prover::new(b"Runtime DLogs", &mut transcript);
+    let (var_G, _) = prover.allocate_point(b"G", G);
+    let mut instance: Vec<CompressedRistretto> = Vec::new();
+    for _ in 0..count {
+        let x = Scalar::random(&mut thread_rng());
+        let var_x = prover.allocate_scalar(b"x", Some(x));
+        let (var_A, cmpr_A) = prover.allocate_point(b"A", G * x);
+        prover.constrain(var_A, vec![(var_x, var_G)]);
+        instance.push(cmpr_A);
+    }
+
+    ProofEnvelope::new("Runtime DLogs", instance, prover.prove_compact().unwrap())
+}
+
+#[test]
+fn registry_lists_and_verifies_runtime_statements() {
+    let G = dalek_constants::RISTRETTO_BASEPOINT_COMPRESSED;
+    let mut registry = StatementRegistry::new();
+    registry.register::<dleq::Statement>(dleq::CommonPoints { G });
+    registry.register_fn("Runtime DLogs", "A_i = (G ^ x_i) for each instance point A_i", verify_dlogs);
+
+    let statements: Vec<(&str, &str)> = registry.statements().collect();
+    assert_eq!(
+        statements,
+        vec![
+            ("DLEQ Envelope Proof", dleq::internal::DESCRIPTION),
+            ("Runtime DLogs", "A_i = (G ^ x_i) for each instance point A_i"),
+        ]
+    );
+    assert!(dleq::internal::DESCRIPTION.contains("A = (G ^ x)"));
+    assert!(registry.contains("Runtime DLogs"));
+    assert_eq!(registry.description("DLog Envelope Proof"), None);
+
+    for count in 1..4 {
+        let bytes = dlogs_envelope(count).to_bytes();
+        assert!(registry
+            .verify_envelope_bytes(&bytes, &mut Transcript::new(b"EnvelopeTest"))
+            .is_ok());
+    }
+
+    // The runtime statement is routed by label like the generated ones
+    let (compact, _) = dleq_envelopes();
+    assert!(registry
+        .verify_envelope(&compact, &mut Transcript::new(b"EnvelopeTest"))
+        .is_ok());
+    let mut truncated = dlogs_envelope(3);
+    truncated.instance.pop();
+    assert!(registry
+        .verify_envelope(&truncated, &mut Transcript::new(b"EnvelopeTest"))
+        .is_err());
+}
@@ -24,11 +24,11 @@ pub use zkp::Transcript;
 define_proof! {sig_proof, "Sig", (x), (A), (B) : A = (B ^ x) }
 define_proof! {vrf_proof, "VRF", (x), (A, G, H), (B) : A = (B ^ x) && G = (H ^ x) }
 
-define_proof! {test_parsing_no_common, "test_parsing_no_common", (x, y), (A, G, H, B), () : A = (B ^ x * H^y) && G = (H ^ x) && G = (H ^ x) }
-define_proof! {test_parsing_no_pub, "test_parsing_no_pub", (x, y), (), (A, G, H, B) : A = (B ^ x * H^y) && G = (H ^ x) && G = (H ^ x) }
+define_proof! {#[skip_statement_analysis] test_parsing_no_common, "test_parsing_no_common", (x, y), (A, G, H, B), () : A = (B ^ x * H^y) && G = (H ^ x) && G = (H ^ x) }
+define_proof! {#[skip_statement_analysis] test_parsing_no_pub, "test_parsing_no_pub", (x, y), (), (A, G, H, B) : A = (B ^ x * H^y) && G = (H ^ x) && G = (H ^ x) }
 define_proof! {test_parsing_or, "test_parsing_or", (x, y), (A, G, H), (B) : A = (B ^ x * H^y) && G = (H ^ x) || G = (H ^ x) }
 define_proof! {test_parsing_sub, "sub", (x, y), (A, G, H), (B) : A = (B ^ x * H^y) && G = (H ^ x) || G = (H ^ x) }
-define_proof! {test_parsing_with_sub, "with_sub", (x, y), (A, G, H), (B) : test_parsing_sub && G = (H ^ x) }
+define_proof! {#[skip_statement_analysis] test_parsing_with_sub, "with_sub", (x, y), (A, G, H), (B) : test_parsing_sub && G = (H ^ x) }
 
 /// Defines how the construction interacts with the transcript.
 trait TranscriptProtocol {
@@ -78,7 +78,7 @@ impl From<SecretKey> for KeyPair {
     }
 }
 
-pub struct Signature(sig_proof::BatchableProof);
+pub struct Signature(sig_proof::CompactProof);
 
 pub struct VrfOutput(CompressedRistretto);
 
@@ -90,11 +90,11 @@ impl KeyPair {
     }
 
     fn sign(&self, message: &[u8], sig_transcript: &mut Transcript) -> Signature {
-        sig_transcript.append_message_example(message);
-        let (proof, _points) = sig_proof::prove_batchable(
+        let (proof, _points) = sig_proof::sign(
+            message,
             sig_transcript,
             sig_proof::ProveAssignments {
-                x: &Some(self.sk.0),
+                witness: &sig_proof::Witness::Clause1 { x: self.sk.0 },
                 A: &self.pk.0,
                 B: &dalek_constants::RISTRETTO_BASEPOINT_POINT,
             },
@@ -115,11 +115,11 @@ impl KeyPair {
         let H = function_transcript.hash_to_group();
 
         // Compute the VRF output G and form a proof
-        let G = &H * &self.sk.0;
+        let G = H * self.sk.0;
         let (proof, points) = vrf_proof::prove_compact(
             proof_transcript,
             vrf_proof::ProveAssignments {
-                x: &Some(self.sk.0),
+                witness: &vrf_proof::Witness::Clause1 { x: self.sk.0 },
                 A: &self.pk.0,
                 B: &dalek_constants::RISTRETTO_BASEPOINT_POINT,
                 G: &G,
@@ -138,8 +138,8 @@ impl Signature {
         pubkey: &PublicKey,
         sig_transcript: &mut Transcript,
     ) -> Result<(), ()> {
-        sig_transcript.append_message_example(message);
-        sig_proof::verify_batchable(
+        sig_proof::verify_signature(
+            message,
             &self.0,
             sig_transcript,
             sig_proof::VerifyAssignments {
@@ -227,12 +227,33 @@ fn create_and_verify_sig() {
         .is_err());
 }
 
+#[test]
+fn signature_is_separated_from_proofs() {
+    let domain_sep = b"My Sig Application";
+    let msg = b"Test Message";
+    let kp = KeyPair::from(SecretKey::new(&mut thread_rng()));
+    let assignments = sig_proof::VerifyAssignments {
+        A: &kp.pk.1,
+        B: &dalek_constants::RISTRETTO_BASEPOINT_COMPRESSED,
+    };
+
+    let sig = kp.sign(&msg[..], &mut Transcript::new(domain_sep));
+    assert!(sig_proof::verify_signature(msg, &sig.0, &mut Transcript::new(domain_sep), assignments).is_ok());
+
+    // The signature isn't a proof for the same transcript without the
+    // message, nor for one with the message appended by hand
+    assert!(sig_proof::verify_compact(&sig.0, &mut Transcript::new(domain_sep), assignments).is_err());
+    let mut transcript = Transcript::new(domain_sep);
+    transcript.append_message_example(msg);
+    assert!(sig_proof::verify_compact(&sig.0, &mut transcript, assignments).is_err());
+}
+
 #[test]
 #[ignore]
 fn create_and_verify_bigsig() {
     let domain_sep = b"My Sig Application";
     let mut large_msg = Vec::new();
-    large_msg.resize((u32::max_value() as usize) + 250, 1u8);
+    large_msg.resize((u32::MAX as usize) + 250, 1u8);
 
     let kp = KeyPair::from(SecretKey::new(&mut thread_rng()));
     let pk = kp.public_key();
@@ -389,3 +410,84 @@ fn create_and_verify_vrf() {
         )
         .is_err());
 }
+
+#[test]
+#[allow(non_snake_case)]
+fn multi_batch_verify_sigs_and_vrfs() {
+    use zkp::toolbox::multi_batch_verifier::MultiBatchVerifier;
+
+    let domain_sep = b"My Mixed Application";
+    let messages: [&[u8]; 3] = [b"Test Message 1", b"Test Message 2", b"Test Message 3"];
+    let B = dalek_constants::RISTRETTO_BASEPOINT_POINT;
+
+    let mut sigs = Vec::new();
+    let mut vrfs = Vec::new();
+    for message in messages.iter() {
+        let kp = KeyPair::from(SecretKey::new(&mut thread_rng()));
+
+        let mut transcript = Transcript::new(domain_sep);
+        transcript.append_message_example(message);
+        let (proof, points) = sig_proof::prove_batchable(
+            &mut transcript,
+            sig_proof::ProveAssignments {
+                witness: &sig_proof::Witness::Clause1 { x: kp.sk.0 },
+                A: &kp.pk.0,
+                B: &B,
+            },
+        )
+        .unwrap();
+        sigs.push((proof, points));
+
+        let mut function_transcript = Transcript::new(domain_sep);
+        function_transcript.append_message_example(message);
+        let H = function_transcript.hash_to_group();
+        let (proof, points) = vrf_proof::prove_batchable(
+            &mut Transcript::new(domain_sep),
+            vrf_proof::ProveAssignments {
+                witness: &vrf_proof::Witness::Clause1 { x: kp.sk.0 },
+                A: &kp.pk.0,
+                B: &B,
+                G: &(H * kp.sk.0),
+                H: &H,
+            },
+        )
+        .unwrap();
+        vrfs.push((proof, points));
+    }
+
+    let verify_all = |sigs: &[(sig_proof::BatchableProof, sig_proof::CompressedPoints)]| {
+        let mut verifier = MultiBatchVerifier::new();
+        for (message, ((sig, sig_points), (vrf, vrf_points))) in messages.iter().zip(sigs.iter().zip(vrfs.iter())) {
+            let mut transcript = Transcript::new(domain_sep);
+            transcript.append_message_example(message);
+            sig_proof::add_to_multi_batch(
+                &mut verifier,
+                sig,
+                &mut transcript,
+                sig_proof::VerifyAssignments {
+                    A: &sig_points.A,
+                    B: &dalek_constants::RISTRETTO_BASEPOINT_COMPRESSED,
+                },
+            )?;
+            vrf_proof::add_to_multi_batch(
+                &mut verifier,
+                vrf,
+                &mut Transcript::new(domain_sep),
+                vrf_proof::VerifyAssignments {
+                    A: &vrf_points.A,
+                    B: &dalek_constants::RISTRETTO_BASEPOINT_COMPRESSED,
+                    G: &vrf_points.G,
+                    H: &vrf_points.H,
+                },
+            )?;
+        }
+        assert_eq!(verifier.len(), 2 * messages.len());
+        verifier.verify()
+    };
+
+    assert!(verify_all(&sigs).is_ok());
+
+    // A single invalid signature fails the whole batch
+    sigs[1].0.responses[0] += Scalar::one();
+    assert!(verify_all(&sigs).is_err());
+}
@@ -0,0 +1,114 @@
+#![allow(non_snake_case)]
+
+extern crate curve25519_dalek;
+extern crate serde;
+extern crate sha2;
+#[macro_use]
+extern crate zkp;
+
+use sha2::Sha512;
+
+use curve25519_dalek::constants as dalek_constants;
+use curve25519_dalek::ristretto::RistrettoPoint;
+
+use zkp::toolbox::analysis::{Issue, StatementAnalyzer};
+use zkp::toolbox::SchnorrCS;
+
+define_proof! {#[skip_statement_analysis] mistakes, "Mistakes", (x, y, z), (A, B, H), (G) : A = (G ^ x * H ^ y) && A = (H ^ y * G ^ x) && B = (H ^ x) }
+
+#[test]
+fn analyzer_reports_issues() {
+    let G = dalek_constants::RISTRETTO_BASEPOINT_COMPRESSED;
+    let H = RistrettoPoint::hash_from_bytes::<Sha512>(b"H").compress();
+
+    let mut analyzer = StatementAnalyzer::new();
+    let x = analyzer.allocate_scalar(b"x");
+    let y = analyzer.allocate_scalar(b"y");
+    let _z = analyzer.allocate_scalar(b"z");
+    let A = analyzer.allocate_point(b"A", Some(G));
+    let B = analyzer.allocate_point(b"B", Some(G));
+    let var_G = analyzer.allocate_point(b"G", Some(G));
+    let var_H = analyzer.allocate_point(b"H", Some(H));
+    let C = analyzer.allocate_point(b"C", None);
+
+    analyzer.constrain(A, vec![(x, var_G), (y, var_H)]);
+    analyzer.constrain(A, vec![(y, var_H), (x, var_G)]);
+    analyzer.constrain(B, vec![(x, var_H)]);
+    analyzer.constrain(C, vec![(y, var_G)]);
+
+    assert_eq!(
+        analyzer.analyze(),
+        vec![
+            Issue::UnusedSecret {
+                label: "z".to_string(),
+            },
+            Issue::DuplicateConstraint {
+                constraint: 1,
+                duplicate_of: 0,
+            },
+            Issue::ReusedLhsPoint {
+                label: "A".to_string(),
+                other_label: "B".to_string(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn constraints_of_different_clauses_are_not_duplicates() {
+    let mut analyzer = StatementAnalyzer::new();
+    let x = analyzer.allocate_scalar(b"x");
+    let A = analyzer.allocate_point(b"A", None);
+    let G = analyzer.allocate_point(b"G", None);
+
+    analyzer.or(|cs| {
+        cs.constrain(A, vec![(x, G)]);
+        cs.constrain(A, vec![(x, G)]);
+    });
+
+    assert!(analyzer.analyze().is_empty());
+}
+
+#[test]
+fn generated_statements_are_analyzed_with_their_points() {
+    let G = dalek_constants::RISTRETTO_BASEPOINT_COMPRESSED;
+    let H = RistrettoPoint::hash_from_bytes::<Sha512>(b"H").compress();
+
+    assert_eq!(
+        mistakes::internal::analyze(mistakes::VerifyAssignments {
+            A: &G,
+            B: &G,
+            H: &H,
+            G: &G,
+        }),
+        vec![
+            Issue::UnusedSecret {
+                label: "z".to_string(),
+            },
+            Issue::DuplicateConstraint {
+                constraint: 1,
+                duplicate_of: 0,
+            },
+            Issue::ReusedLhsPoint {
+                label: "A".to_string(),
+                other_label: "B".to_string(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn generated_statements_are_analyzed_without_points() {
+    assert_eq!(
+        mistakes::internal::analyze_statement(),
+        vec![
+            Issue::UnusedSecret {
+                label: "z".to_string(),
+            },
+            Issue::DuplicateConstraint {
+                constraint: 1,
+                duplicate_of: 0,
+            },
+        ]
+    );
+}
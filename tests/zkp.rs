@@ -26,6 +26,50 @@ use curve25519_dalek::scalar::Scalar;
 use zkp::Transcript;
 
 define_proof! {dleq, "DLEQ Example Proof", (x), (A, B, H), (G) : A = (G ^ x) && B = (H ^ x) }
+define_proof! {#[skip_statement_analysis] repeated, "Repeated", (x), (A), (G) : A = (G ^ x) && A = (G ^ x) }
+define_proof! {#[skip_statement_analysis] #[normalize] normalized, "Repeated", (x), (A), (G) : A = (G ^ x) && A = (G ^ x) }
+
+/// Create `n` batchable DLEQ proofs, each with its own secret and
+/// input point, on transcripts labeled `DLEQTest`.
+fn dleq_proofs(n: usize) -> (Vec<dleq::BatchableProof>, Vec<dleq::CompressedPoints>) {
+    (0..n)
+        .map(|i| {
+            let H = RistrettoPoint::hash_from_bytes::<Sha512>(&[i as u8]);
+            let x = Scalar::from(89327492234u64) * Scalar::from((i + 1) as u64);
+            let A = &x * &dalek_constants::RISTRETTO_BASEPOINT_TABLE;
+            let B = x * H;
+
+            let mut transcript = Transcript::new(b"DLEQTest");
+            dleq::prove_batchable(
+                &mut transcript,
+                dleq::ProveAssignments {
+                    witness: &dleq::Witness::Clause1 { x },
+                    A: &A,
+                    B: &B,
+                    G: &dalek_constants::RISTRETTO_BASEPOINT_POINT,
+                    H: &H,
+                },
+            )
+            .unwrap()
+        })
+        .unzip()
+}
+
+fn dleq_batch_verify_assignments(points: &[dleq::CompressedPoints]) -> dleq::BatchVerifyAssignments {
+    dleq::BatchVerifyAssignments {
+        A: points.iter().map(|p| p.A).collect(),
+        B: points.iter().map(|p| p.B).collect(),
+        H: points.iter().map(|p| p.H).collect(),
+        G: dalek_constants::RISTRETTO_BASEPOINT_COMPRESSED,
+    }
+}
+
+fn dleq_instances(points: &[dleq::CompressedPoints]) -> Vec<dleq::InstanceAssignments> {
+    points
+        .iter()
+        .map(|p| dleq::InstanceAssignments { A: p.A, B: p.B, H: p.H })
+        .collect()
+}
 
 #[test]
 fn create_and_verify_compact() {
@@ -34,13 +78,13 @@ fn create_and_verify_compact() {
         let H = RistrettoPoint::hash_from_bytes::<Sha512>(b"A VRF input, for instance");
         let x = Scalar::from(89327492234u64).invert();
         let A = &x * &dalek_constants::RISTRETTO_BASEPOINT_TABLE;
-        let B = &x * &H;
+        let B = x * H;
 
         let mut transcript = Transcript::new(b"DLEQTest");
         dleq::prove_compact(
             &mut transcript,
             dleq::ProveAssignments {
-                x: &Some(x),
+                witness: &dleq::Witness::Clause1 { x },
                 A: &A,
                 B: &B,
                 G: &dalek_constants::RISTRETTO_BASEPOINT_POINT,
@@ -77,13 +121,13 @@ fn create_and_verify_batchable() {
         let H = RistrettoPoint::hash_from_bytes::<Sha512>(b"A VRF input, for instance");
         let x = Scalar::from(89327492234u64).invert();
         let A = &x * &dalek_constants::RISTRETTO_BASEPOINT_TABLE;
-        let B = &x * &H;
+        let B = x * H;
 
         let mut transcript = Transcript::new(b"DLEQTest");
         dleq::prove_batchable(
             &mut transcript,
             dleq::ProveAssignments {
-                x: &Some(x),
+                witness: &dleq::Witness::Clause1 { x },
                 A: &A,
                 B: &B,
                 G: &dalek_constants::RISTRETTO_BASEPOINT_POINT,
@@ -130,13 +174,13 @@ fn create_batch_and_batch_verify() {
             let H = RistrettoPoint::hash_from_bytes::<Sha512>(message.as_bytes());
             let x = Scalar::from(89327492234u64) * Scalar::from((i + 1) as u64);
             let A = &x * &dalek_constants::RISTRETTO_BASEPOINT_TABLE;
-            let B = &x * &H;
+            let B = x * H;
 
             let mut transcript = Transcript::new(b"DLEQTest");
             let (proof, points) = dleq::prove_batchable(
                 &mut transcript,
                 dleq::ProveAssignments {
-                    x: &Some(x),
+                    witness: &dleq::Witness::Clause1 { x },
                     A: &A,
                     B: &B,
                     G: &dalek_constants::RISTRETTO_BASEPOINT_POINT,
@@ -173,3 +217,780 @@ fn create_batch_and_batch_verify() {
     )
     .is_ok());
 }
+
+#[test]
+fn create_and_verify_with_rng_is_deterministic() {
+    use zkp::rand::rngs::StdRng;
+    use zkp::rand::SeedableRng;
+
+    let H = RistrettoPoint::hash_from_bytes::<Sha512>(b"A VRF input, for instance");
+    let x = Scalar::from(89327492234u64).invert();
+    let A = &x * &dalek_constants::RISTRETTO_BASEPOINT_TABLE;
+    let B = x * H;
+
+    let prove = |seed: u64| {
+        let mut transcript = Transcript::new(b"DLEQTest");
+        dleq::prove_batchable_with_rng(
+            &mut transcript,
+            dleq::ProveAssignments {
+                witness: &dleq::Witness::Clause1 { x },
+                A: &A,
+                B: &B,
+                G: &dalek_constants::RISTRETTO_BASEPOINT_POINT,
+                H: &H,
+            },
+            &mut StdRng::seed_from_u64(seed),
+        )
+        .unwrap()
+    };
+
+    let (proof, points) = prove(42);
+    let (same_proof, _) = prove(42);
+    let (other_proof, _) = prove(43);
+
+    // The same seed reproduces the same proof, a different one doesn't
+    let proof_bytes = bincode::serialize(&proof).unwrap();
+    assert_eq!(proof_bytes, bincode::serialize(&same_proof).unwrap());
+    assert_ne!(proof_bytes, bincode::serialize(&other_proof).unwrap());
+
+    let mut transcript = Transcript::new(b"DLEQTest");
+    assert!(dleq::verify_batchable_with_rng(
+        &proof,
+        &mut transcript,
+        dleq::VerifyAssignments {
+            A: &points.A,
+            B: &points.B,
+            G: &dalek_constants::RISTRETTO_BASEPOINT_COMPRESSED,
+            H: &points.H,
+        },
+        &mut StdRng::seed_from_u64(7),
+    )
+    .is_ok());
+}
+
+#[test]
+fn create_and_verify_with_zeroizing_secrets() {
+    use zkp::Zeroizing;
+
+    let H = RistrettoPoint::hash_from_bytes::<Sha512>(b"A VRF input, for instance");
+    let x = Zeroizing::new(Scalar::from(89327492234u64).invert());
+    let A = &*x * &dalek_constants::RISTRETTO_BASEPOINT_TABLE;
+    let B = *x * H;
+
+    // The witness zeroizes itself, and can also be wrapped in Zeroizing
+    let witness = Zeroizing::new(dleq::Witness::Clause1 { x: *x });
+
+    let mut transcript = Transcript::new(b"DLEQTest");
+    let (proof, points) = dleq::prove_compact(
+        &mut transcript,
+        dleq::ProveAssignments {
+            witness: &witness,
+            A: &A,
+            B: &B,
+            G: &dalek_constants::RISTRETTO_BASEPOINT_POINT,
+            H: &H,
+        },
+    )
+    .unwrap();
+    drop(witness);
+    drop(x);
+
+    let mut transcript = Transcript::new(b"DLEQTest");
+    assert!(dleq::verify_compact(
+        &proof,
+        &mut transcript,
+        dleq::VerifyAssignments {
+            A: &points.A,
+            B: &points.B,
+            G: &dalek_constants::RISTRETTO_BASEPOINT_COMPRESSED,
+            H: &points.H,
+        },
+    )
+    .is_ok());
+}
+
+#[test]
+fn batch_verify_finds_invalid_proofs() {
+    use curve25519_dalek::ristretto::CompressedRistretto;
+
+    let batch_size = 16;
+
+    let (mut proofs, points) = dleq_proofs(batch_size);
+    let mut assignments = dleq_batch_verify_assignments(&points);
+
+    // Break proof 3 and 11, make proof 7 malformed, and give proof 12 an identity point
+    proofs[3].responses[0] += Scalar::one();
+    assignments.B.swap(11, 0);
+    proofs[7].commitments.pop();
+    assignments.H[12] = CompressedRistretto::default();
+
+    let mut transcripts = vec![Transcript::new(b"DLEQTest"); batch_size];
+    let invalid =
+        dleq::batch_verify_find_invalid(&proofs, transcripts.iter_mut().collect(), assignments.clone())
+            .unwrap();
+
+    assert_eq!(invalid, vec![0, 3, 7, 11, 12]);
+
+    // An invalid common point is reported as such, not blamed on every proof
+    assignments.G = CompressedRistretto([0xff; 32]);
+    let mut transcripts = vec![Transcript::new(b"DLEQTest"); batch_size];
+    match dleq::batch_verify_find_invalid(&proofs, transcripts.iter_mut().collect(), assignments) {
+        Err(zkp::ProofError::InvalidPoint { label }) => assert_eq!(label, "G"),
+        result => panic!("unexpected result {:?}", result),
+    }
+}
+
+#[test]
+fn prepared_verifier_verifies_batchable_proofs() {
+    let batch_size = 4;
+
+    let (proofs, points) = dleq_proofs(batch_size);
+    let mut instances = dleq_instances(&points);
+
+    let verifier = dleq::PreparedVerifier::new(dleq::CommonAssignments {
+        G: dalek_constants::RISTRETTO_BASEPOINT_COMPRESSED,
+    })
+    .unwrap();
+
+    // The same prepared verifier checks each proof on its own...
+    for (proof, instance) in proofs.iter().zip(instances.iter()) {
+        let mut transcript = Transcript::new(b"DLEQTest");
+        assert!(verifier
+            .verify_batchable(proof, &mut transcript, *instance)
+            .is_ok());
+    }
+
+    // ...and the whole batch at once.
+    let mut transcripts = vec![Transcript::new(b"DLEQTest"); batch_size];
+    assert!(verifier
+        .batch_verify(&proofs, transcripts.iter_mut().collect(), &instances)
+        .is_ok());
+
+    // A proof checked against another instance is rejected.
+    let mut transcript = Transcript::new(b"DLEQTest");
+    assert!(verifier
+        .verify_batchable(&proofs[0], &mut transcript, instances[1])
+        .is_err());
+
+    instances.swap(1, 2);
+    let mut transcripts = vec![Transcript::new(b"DLEQTest"); batch_size];
+    assert!(verifier
+        .batch_verify(&proofs, transcripts.iter_mut().collect(), &instances)
+        .is_err());
+}
+
+#[test]
+fn prepared_prover_matches_prover() {
+    use zkp::rand::rngs::StdRng;
+    use zkp::rand::SeedableRng;
+
+    let H = RistrettoPoint::hash_from_bytes::<Sha512>(b"A VRF input, for instance");
+    let x = Scalar::from(89327492234u64).invert();
+    let A = &x * &dalek_constants::RISTRETTO_BASEPOINT_TABLE;
+    let B = x * H;
+
+    let prover = dleq::PreparedProver::new(dleq::CommonTables {
+        G: dalek_constants::RISTRETTO_BASEPOINT_TABLE.clone(),
+    });
+
+    let mut transcript = Transcript::new(b"DLEQTest");
+    let (proof, points) = prover
+        .prove_batchable_with_rng(
+            &mut transcript,
+            dleq::ProveInstanceAssignments {
+                witness: &dleq::Witness::Clause1 { x },
+                A: &A,
+                B: &B,
+                H: &H,
+            },
+            &mut StdRng::seed_from_u64(42),
+        )
+        .unwrap();
+
+    let mut transcript = Transcript::new(b"DLEQTest");
+    let (expected_proof, _) = dleq::prove_batchable_with_rng(
+        &mut transcript,
+        dleq::ProveAssignments {
+            witness: &dleq::Witness::Clause1 { x },
+            A: &A,
+            B: &B,
+            G: &dalek_constants::RISTRETTO_BASEPOINT_POINT,
+            H: &H,
+        },
+        &mut StdRng::seed_from_u64(42),
+    )
+    .unwrap();
+
+    // The tables only speed up the prover; the proof is unchanged
+    assert_eq!(
+        bincode::serialize(&proof).unwrap(),
+        bincode::serialize(&expected_proof).unwrap()
+    );
+    assert_eq!(points.G, dalek_constants::RISTRETTO_BASEPOINT_COMPRESSED);
+
+    let mut transcript = Transcript::new(b"DLEQTest");
+    assert!(dleq::verify_batchable(
+        &proof,
+        &mut transcript,
+        dleq::VerifyAssignments {
+            A: &points.A,
+            B: &points.B,
+            G: &points.G,
+            H: &points.H,
+        },
+    )
+    .is_ok());
+}
+
+#[test]
+fn parallel_batch_verify_matches_sequential() {
+    let batch_size = 10;
+
+    let (mut proofs, points) = dleq_proofs(batch_size);
+
+    let assignments = dleq_batch_verify_assignments(&points);
+    let verify = |proofs: &[dleq::BatchableProof], num_threads: Option<usize>| {
+        let mut transcripts = vec![Transcript::new(b"DLEQTest"); batch_size];
+        let transcripts = transcripts.iter_mut().collect();
+        let result = match num_threads {
+            None => dleq::batch_verify(proofs, transcripts, assignments.clone()),
+            Some(n) => dleq::batch_verify_parallel(proofs, transcripts, assignments.clone(), n),
+        };
+        result.map_err(|e| e.to_string())
+    };
+
+    // Including more threads than proofs, and zero meaning one
+    for &num_threads in &[0, 1, 3, 4, 16] {
+        assert_eq!(verify(&proofs, Some(num_threads)), verify(&proofs, None));
+        assert!(verify(&proofs, Some(num_threads)).is_ok());
+    }
+
+    proofs[6].commitments.pop();
+    for &num_threads in &[1, 3, 16] {
+        assert_eq!(verify(&proofs, Some(num_threads)), verify(&proofs, None));
+        assert!(verify(&proofs, Some(num_threads)).is_err());
+    }
+
+    let commitment = proofs[5].commitments[0];
+    proofs[6].commitments.push(commitment);
+    for &num_threads in &[1, 3, 16] {
+        assert_eq!(verify(&proofs, Some(num_threads)), verify(&proofs, None));
+        assert!(verify(&proofs, Some(num_threads)).is_err());
+    }
+}
+
+#[test]
+fn streaming_verifier_checks_proofs_in_chunks() {
+    let num_proofs = 10;
+
+    let (mut proofs, points) = dleq_proofs(num_proofs);
+    let instances = dleq_instances(&points);
+
+    let verifier = dleq::PreparedVerifier::new(dleq::CommonAssignments {
+        G: dalek_constants::RISTRETTO_BASEPOINT_COMPRESSED,
+    })
+    .unwrap();
+
+    let mut stream = verifier.streaming_verifier(3);
+    for (proof, instance) in proofs.iter().zip(instances.iter()) {
+        let mut transcript = Transcript::new(b"DLEQTest");
+        assert!(stream.push(proof, &mut transcript, *instance).is_ok());
+    }
+    assert_eq!(stream.len(), num_proofs);
+    assert!(stream.finish().is_ok());
+
+    // Make proof 4 malformed and proof 7 invalid
+    proofs[4].commitments.pop();
+    proofs[7].responses[0] += Scalar::one();
+
+    let mut stream = verifier.streaming_verifier(3);
+    let results = proofs
+        .iter()
+        .zip(instances.iter())
+        .map(|(proof, instance)| {
+            let mut transcript = Transcript::new(b"DLEQTest");
+            stream.push(proof, &mut transcript, *instance)
+        })
+        .collect::<Vec<_>>();
+
+    // The malformed proof is rejected when it is pushed, and the
+    // invalid one when its chunk of 3 well-formed proofs is complete.
+    for (i, result) in results.iter().enumerate() {
+        match (i, result) {
+            (4, Err(zkp::ProofError::BatchProofFailure { index: 4, source })) => {
+                assert!(matches!(**source, zkp::ProofError::WrongCommitmentCount { .. }));
+            }
+            (9, Err(zkp::ProofError::VerificationFailure)) => {}
+            (_, result) => assert!(result.is_ok(), "unexpected result for proof {}", i),
+        }
+    }
+    assert!(matches!(
+        stream.finish(),
+        Err(zkp::ProofError::BatchProofFailure { index: 4, .. })
+    ));
+}
+
+#[test]
+fn batch_prove_matches_individual_proofs() {
+    use zkp::rand::rngs::StdRng;
+    use zkp::rand::SeedableRng;
+
+    let batch_size = 5;
+
+    let secrets = (0..batch_size)
+        .map(|i| Scalar::from(89327492234u64) * Scalar::from((i + 1) as u64))
+        .collect::<Vec<_>>();
+    let witnesses = secrets
+        .iter()
+        .map(|&x| dleq::Witness::Clause1 { x })
+        .collect::<Vec<_>>();
+    let inputs = (0..batch_size)
+        .map(|i| RistrettoPoint::hash_from_bytes::<Sha512>(&[i as u8]))
+        .collect::<Vec<_>>();
+    let pubkeys = secrets
+        .iter()
+        .map(|x| x * &dalek_constants::RISTRETTO_BASEPOINT_TABLE)
+        .collect::<Vec<_>>();
+    let vrf_outputs = secrets
+        .iter()
+        .zip(inputs.iter())
+        .map(|(x, H)| x * H)
+        .collect::<Vec<_>>();
+
+    let assignments = (0..batch_size)
+        .map(|i| dleq::ProveInstanceAssignments {
+            witness: &witnesses[i],
+            A: &pubkeys[i],
+            B: &vrf_outputs[i],
+            H: &inputs[i],
+        })
+        .collect::<Vec<_>>();
+
+    let prover = dleq::PreparedProver::new(dleq::CommonTables {
+        G: dalek_constants::RISTRETTO_BASEPOINT_TABLE.clone(),
+    });
+
+    let mut transcripts = vec![Transcript::new(b"DLEQTest"); batch_size];
+    let batch = prover
+        .batch_prove_batchable_with_rng(
+            transcripts.iter_mut().collect(),
+            &assignments,
+            &mut StdRng::seed_from_u64(42),
+        )
+        .unwrap();
+
+    // The batch is the same as proving each instance in turn
+    let mut rng = StdRng::seed_from_u64(42);
+    for ((proof, points), assignment) in batch.iter().zip(assignments.iter()) {
+        let mut transcript = Transcript::new(b"DLEQTest");
+        let (expected_proof, expected_points) = prover
+            .prove_batchable_with_rng(&mut transcript, *assignment, &mut rng)
+            .unwrap();
+        assert_eq!(
+            bincode::serialize(proof).unwrap(),
+            bincode::serialize(&expected_proof).unwrap()
+        );
+        assert_eq!(points.A, expected_points.A);
+        assert_eq!(points.G, expected_points.G);
+    }
+
+    // Proofs created on several threads verify
+    let mut transcripts = vec![Transcript::new(b"DLEQTest"); batch_size];
+    let batch = prover
+        .batch_prove_batchable_parallel(transcripts.iter_mut().collect(), &assignments, 2)
+        .unwrap();
+    let (proofs, points): (Vec<_>, Vec<_>) = batch.into_iter().unzip();
+
+    let mut transcripts = vec![Transcript::new(b"DLEQTest"); batch_size];
+    assert!(dleq::batch_verify(
+        &proofs,
+        transcripts.iter_mut().collect(),
+        dleq::BatchVerifyAssignments {
+            A: points.iter().map(|p| p.A).collect(),
+            B: points.iter().map(|p| p.B).collect(),
+            H: points.iter().map(|p| p.H).collect(),
+            G: dalek_constants::RISTRETTO_BASEPOINT_COMPRESSED,
+        },
+    )
+    .is_ok());
+
+    // Given the same rng, proving on several threads is reproducible
+    let prove_parallel = |seed: u64| {
+        let mut transcripts = vec![Transcript::new(b"DLEQTest"); batch_size];
+        prover
+            .batch_prove_batchable_parallel_with_rng(
+                transcripts.iter_mut().collect(),
+                &assignments,
+                2,
+                &mut StdRng::seed_from_u64(seed),
+            )
+            .unwrap()
+            .iter()
+            .map(|(proof, _points)| bincode::serialize(proof).unwrap())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(prove_parallel(7), prove_parallel(7));
+    assert_ne!(prove_parallel(7), prove_parallel(8));
+
+    // Compact proofs created on several threads verify
+    let mut transcripts = vec![Transcript::new(b"DLEQTest"); batch_size];
+    let batch = prover
+        .batch_prove_compact_parallel(transcripts.iter_mut().collect(), &assignments, 2)
+        .unwrap();
+    for (proof, points) in batch.iter() {
+        let mut transcript = Transcript::new(b"DLEQTest");
+        assert!(dleq::verify_compact(
+            proof,
+            &mut transcript,
+            dleq::VerifyAssignments {
+                A: &points.A,
+                B: &points.B,
+                G: &points.G,
+                H: &points.H,
+            },
+        )
+        .is_ok());
+    }
+
+    // Mismatched batch sizes are rejected
+    let mut transcripts = vec![Transcript::new(b"DLEQTest"); batch_size - 1];
+    assert!(matches!(
+        prover.batch_prove_batchable(transcripts.iter_mut().collect(), &assignments),
+        Err(zkp::ProofError::BatchSizeMismatch)
+    ));
+
+    // The module functions take the same assignments as prove_*, and
+    // give the same proofs, even if the common points differ
+    let mut generators = vec![dalek_constants::RISTRETTO_BASEPOINT_POINT; batch_size];
+    generators[1] = inputs[0];
+    let pubkeys = secrets
+        .iter()
+        .zip(generators.iter())
+        .map(|(x, G)| x * G)
+        .collect::<Vec<_>>();
+    let assignments = (0..batch_size)
+        .map(|i| dleq::ProveAssignments {
+            witness: &witnesses[i],
+            A: &pubkeys[i],
+            B: &vrf_outputs[i],
+            G: &generators[i],
+            H: &inputs[i],
+        })
+        .collect::<Vec<_>>();
+
+    let mut transcripts = vec![Transcript::new(b"DLEQTest"); batch_size];
+    let batch = dleq::batch_prove_compact_with_rng(
+        transcripts.iter_mut().collect(),
+        &assignments,
+        &mut StdRng::seed_from_u64(7),
+    )
+    .unwrap();
+
+    let mut rng = StdRng::seed_from_u64(7);
+    for ((proof, points), assignment) in batch.iter().zip(assignments.iter()) {
+        let mut transcript = Transcript::new(b"DLEQTest");
+        let (expected_proof, expected_points) =
+            dleq::prove_compact_with_rng(&mut transcript, *assignment, &mut rng).unwrap();
+        assert_eq!(
+            bincode::serialize(proof).unwrap(),
+            bincode::serialize(&expected_proof).unwrap()
+        );
+        assert_eq!(points, &expected_points);
+    }
+
+    let mut transcripts = vec![Transcript::new(b"DLEQTest"); batch_size];
+    let batch = dleq::batch_prove_batchable(transcripts.iter_mut().collect(), &assignments).unwrap();
+    for (proof, points) in batch.iter() {
+        let mut transcript = Transcript::new(b"DLEQTest");
+        assert!(dleq::verify_batchable(proof, &mut transcript, points.verify_assignments()).is_ok());
+    }
+}
+
+#[test]
+fn create_and_verify_with_precommitment() {
+    let H = RistrettoPoint::hash_from_bytes::<Sha512>(b"A VRF input, for instance");
+    let x = Scalar::from(89327492234u64).invert();
+    let A = &x * &dalek_constants::RISTRETTO_BASEPOINT_TABLE;
+    let B = x * H;
+
+    let assignments = dleq::ProveAssignments {
+        witness: &dleq::Witness::Clause1 { x },
+        A: &A,
+        B: &B,
+        G: &dalek_constants::RISTRETTO_BASEPOINT_POINT,
+        H: &H,
+    };
+
+    // Offline phase, before the context of the proof is known
+    let precommitment = dleq::precommit(assignments).unwrap();
+
+    // Online phase
+    let mut transcript = Transcript::new(b"DLEQTest");
+    transcript.append_message(b"msg", b"A message known only later");
+    let (proof, points) =
+        dleq::prove_compact_with_precommitment(&mut transcript, assignments, precommitment).unwrap();
+
+    let mut transcript = Transcript::new(b"DLEQTest");
+    transcript.append_message(b"msg", b"A message known only later");
+    assert!(dleq::verify_compact(
+        &proof,
+        &mut transcript,
+        dleq::VerifyAssignments {
+            A: &points.A,
+            B: &points.B,
+            G: &points.G,
+            H: &points.H,
+        },
+    )
+    .is_ok());
+
+    // A precommitment for other points can't be used
+    let other_H = RistrettoPoint::hash_from_bytes::<Sha512>(b"Another VRF input");
+    let other_B = x * other_H;
+    let precommitment = dleq::precommit(dleq::ProveAssignments {
+        H: &other_H,
+        B: &other_B,
+        ..assignments
+    })
+    .unwrap();
+    let mut transcript = Transcript::new(b"DLEQTest");
+    assert!(matches!(
+        dleq::prove_batchable_with_precommitment(&mut transcript, assignments, precommitment),
+        Err(zkp::ProofError::PrecommitmentMismatch)
+    ));
+
+    // Nor can a precommitment for other secrets
+    let precommitment = dleq::precommit(dleq::ProveAssignments {
+        witness: &dleq::Witness::Clause1 { x: x + Scalar::one() },
+        ..assignments
+    })
+    .unwrap();
+    let mut transcript = Transcript::new(b"DLEQTest");
+    assert!(matches!(
+        dleq::prove_batchable_with_precommitment(&mut transcript, assignments, precommitment),
+        Err(zkp::ProofError::PrecommitmentMismatch)
+    ));
+}
+
+#[test]
+fn precommitments_from_the_same_seeded_rng_differ() {
+    use zkp::rand::rngs::StdRng;
+    use zkp::rand::SeedableRng;
+
+    let H = RistrettoPoint::hash_from_bytes::<Sha512>(b"A VRF input, for instance");
+    let x = Scalar::from(89327492234u64).invert();
+    let A = &x * &dalek_constants::RISTRETTO_BASEPOINT_TABLE;
+    let B = x * H;
+
+    let assignments = dleq::ProveAssignments {
+        witness: &dleq::Witness::Clause1 { x },
+        A: &A,
+        B: &B,
+        G: &dalek_constants::RISTRETTO_BASEPOINT_POINT,
+        H: &H,
+    };
+
+    // Fresh randomness is mixed in, so the nonces of two precommitments
+    // never repeat, even if the caller's rng does
+    let proofs = (0..2)
+        .map(|_| {
+            let precommitment =
+                dleq::precommit_with_rng(assignments, &mut StdRng::seed_from_u64(42)).unwrap();
+            let mut transcript = Transcript::new(b"DLEQTest");
+            let (proof, _points) =
+                dleq::prove_compact_with_precommitment(&mut transcript, assignments, precommitment)
+                    .unwrap();
+            bincode::serialize(&proof).unwrap()
+        })
+        .collect::<Vec<_>>();
+    assert_ne!(proofs[0], proofs[1]);
+}
+
+#[test]
+fn convert_batchable_to_compact() {
+    let H = RistrettoPoint::hash_from_bytes::<Sha512>(b"A VRF input, for instance");
+    let x = Scalar::from(89327492234u64).invert();
+    let A = &x * &dalek_constants::RISTRETTO_BASEPOINT_TABLE;
+    let B = x * H;
+
+    let mut transcript = Transcript::new(b"DLEQTest");
+    let (mut proof, points) = dleq::prove_batchable(
+        &mut transcript,
+        dleq::ProveAssignments {
+            witness: &dleq::Witness::Clause1 { x },
+            A: &A,
+            B: &B,
+            G: &dalek_constants::RISTRETTO_BASEPOINT_POINT,
+            H: &H,
+        },
+    )
+    .unwrap();
+
+    let assignments = dleq::VerifyAssignments {
+        A: &points.A,
+        B: &points.B,
+        G: &points.G,
+        H: &points.H,
+    };
+
+    let mut transcript = Transcript::new(b"DLEQTest");
+    let compact = dleq::batchable_to_compact(&proof, &mut transcript, assignments).unwrap();
+
+    let mut transcript = Transcript::new(b"DLEQTest");
+    assert!(dleq::verify_compact(&compact, &mut transcript, assignments).is_ok());
+
+    // A batchable proof with a wrong commitment can't be converted
+    proof.commitments.swap(0, 1);
+    let mut transcript = Transcript::new(b"DLEQTest");
+    assert!(dleq::batchable_to_compact(&proof, &mut transcript, assignments).is_err());
+}
+
+/// Prove and verify a statement in both formats, knowing only that it
+/// implements [`Statement`](zkp::toolbox::Statement).
+fn prove_and_verify<S: zkp::toolbox::Statement>(
+    prove_assignments: S::ProveAssignments<'_>,
+    verify_assignments: for<'p> fn(&'p S::CompressedPoints) -> S::VerifyAssignments<'p>,
+) -> Result<(), zkp::ProofError>
+where
+    for<'a> S::ProveAssignments<'a>: Copy,
+{
+    let mut transcript = Transcript::new(b"StatementTest");
+    let (proof, points) = S::prove_compact(&mut transcript, prove_assignments)?;
+    let mut transcript = Transcript::new(b"StatementTest");
+    S::verify_compact(&proof, &mut transcript, verify_assignments(&points))?;
+
+    let mut transcript = Transcript::new(b"StatementTest");
+    let (proof, points) = S::prove_batchable(&mut transcript, prove_assignments)?;
+    let mut transcript = Transcript::new(b"StatementTest");
+    S::verify_batchable(&proof, &mut transcript, verify_assignments(&points))
+}
+
+fn dleq_verify_assignments(points: &dleq::CompressedPoints) -> dleq::VerifyAssignments<'_> {
+    dleq::VerifyAssignments {
+        A: &points.A,
+        B: &points.B,
+        G: &points.G,
+        H: &points.H,
+    }
+}
+
+#[test]
+fn generated_statement_implements_trait() {
+    use zkp::toolbox::Statement;
+
+    assert_eq!(dleq::Statement::PROOF_LABEL, "DLEQ Example Proof");
+
+    let H = RistrettoPoint::hash_from_bytes::<Sha512>(b"A VRF input, for instance");
+    let x = Scalar::from(89327492234u64);
+    let A = &x * &dalek_constants::RISTRETTO_BASEPOINT_TABLE;
+    let B = x * H;
+    let witness = dleq::Witness::Clause1 { x };
+    let assignments = dleq::ProveAssignments {
+        witness: &witness,
+        A: &A,
+        B: &B,
+        G: &dalek_constants::RISTRETTO_BASEPOINT_POINT,
+        H: &H,
+    };
+
+    assert!(prove_and_verify::<dleq::Statement>(assignments, dleq_verify_assignments).is_ok());
+
+    // A proof for the wrong points fails through the trait too
+    let assignments = dleq::ProveAssignments { B: &A, ..assignments };
+    assert!(prove_and_verify::<dleq::Statement>(assignments, dleq_verify_assignments).is_err());
+}
+
+#[test]
+fn owned_assignments_serialize_for_later_verification() {
+    let G = dalek_constants::RISTRETTO_BASEPOINT_POINT;
+    let messages = ["One message", "Another message"];
+
+    // The prover stores its owned assignments, and the queue stores
+    // the proofs next to their serialized statement instances
+    let mut queue = Vec::new();
+    for (i, message) in messages.iter().enumerate() {
+        let H = RistrettoPoint::hash_from_bytes::<Sha512>(message.as_bytes());
+        let x = Scalar::from(89327492234u64) * Scalar::from((i + 1) as u64);
+        let assignments = dleq::OwnedProveAssignments {
+            witness: dleq::Witness::Clause1 { x },
+            A: G * x,
+            B: H * x,
+            G,
+            H,
+        };
+        #[cfg(feature = "serialize-witness")]
+        let assignments: dleq::OwnedProveAssignments =
+            bincode::deserialize(&bincode::serialize(&assignments).unwrap()).unwrap();
+
+        let mut transcript = Transcript::new(b"DLEQTest");
+        let (proof, points) = dleq::prove_batchable(&mut transcript, assignments.prove_assignments()).unwrap();
+        assert_eq!(points.common().G, G.compress());
+        let parsed_points: dleq::CompressedPoints =
+            bincode::deserialize(&bincode::serialize(&points).unwrap()).unwrap();
+        assert_eq!(parsed_points, points);
+
+        queue.push((
+            bincode::serialize(&proof).unwrap(),
+            bincode::serialize(&points.instance()).unwrap(),
+        ));
+    }
+
+    // The verifier supplies the common points it expects
+    let common = dleq::CommonPoints {
+        G: dalek_constants::RISTRETTO_BASEPOINT_COMPRESSED,
+    };
+    let mut proofs = Vec::new();
+    let mut instances = Vec::new();
+    for (proof, instance) in queue.iter() {
+        let proof: dleq::BatchableProof = bincode::deserialize(proof).unwrap();
+        let instance: dleq::Instance = bincode::deserialize(instance).unwrap();
+
+        let points = dleq::CompressedPoints::from_parts(&instance, &common);
+        let mut transcript = Transcript::new(b"DLEQTest");
+        assert!(dleq::verify_batchable(&proof, &mut transcript, points.verify_assignments()).is_ok());
+
+        proofs.push(proof);
+        instances.push(instance);
+    }
+
+    let assignments = dleq::BatchVerifyAssignments::from_instances(&instances, &common);
+    let assignments: dleq::BatchVerifyAssignments =
+        bincode::deserialize(&bincode::serialize(&assignments).unwrap()).unwrap();
+    let mut transcripts = vec![Transcript::new(b"DLEQTest"); messages.len()];
+    assert!(dleq::batch_verify(&proofs, transcripts.iter_mut().collect(), assignments).is_ok());
+}
+
+#[test]
+fn statements_are_only_normalized_with_the_flag() {
+    let G = dalek_constants::RISTRETTO_BASEPOINT_POINT;
+    let x = Scalar::from(89327492234u64);
+    let A = G * x;
+
+    let mut transcript = Transcript::new(b"NormalizeTest");
+    let (proof, points) = repeated::prove_batchable(
+        &mut transcript,
+        repeated::ProveAssignments {
+            witness: &repeated::Witness::Clause1 { x },
+            A: &A,
+            G: &G,
+        },
+    )
+    .unwrap();
+    assert_eq!(proof.commitments.len(), 2);
+    let mut transcript = Transcript::new(b"NormalizeTest");
+    assert!(repeated::verify_batchable(&proof, &mut transcript, points.verify_assignments()).is_ok());
+
+    let mut transcript = Transcript::new(b"NormalizeTest");
+    let (proof, points) = normalized::prove_batchable(
+        &mut transcript,
+        normalized::ProveAssignments {
+            witness: &normalized::Witness::Clause1 { x },
+            A: &A,
+            G: &G,
+        },
+    )
+    .unwrap();
+    assert_eq!(proof.commitments.len(), 1);
+    let mut transcript = Transcript::new(b"NormalizeTest");
+    assert!(normalized::verify_batchable(&proof, &mut transcript, points.verify_assignments()).is_ok());
+}